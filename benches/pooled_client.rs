@@ -0,0 +1,106 @@
+//
+// Compares a fresh hyper client per call (what HttpRequest::post did before
+// the shared pool) against one pooled HttpRequest, querying a few hundred
+// local devices that each count the connections they accept.
+//
+//     cargo bench --bench pooled_client
+//
+use criterion::{criterion_group, criterion_main, Criterion, SamplingMode};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use my_onvif::onvif::device_info::{get_action, Http, HttpClientConfig, HttpRequest, DEVICE_WSDL};
+use my_onvif::onvif::messages::{device, to_envelope};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use std::time::Duration;
+
+const DEVICES: usize = 300;
+const CALLS_PER_DEVICE: usize = 5;
+
+const GET_SCOPES_RESPONSE: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<SOAP-ENV:Envelope xmlns:SOAP-ENV=\"http://www.w3.org/2003/05/soap-envelope\" xmlns:tds=\"http://www.onvif.org/ver10/device/wsdl\"><SOAP-ENV:Body><tds:GetScopesResponse></tds:GetScopesResponse></SOAP-ENV:Body></SOAP-ENV:Envelope>";
+
+// Starts a device answering every request with an empty GetScopes response.
+fn start_device(connections: Arc<AtomicUsize>) -> String {
+    let make_service = make_service_fn(move |_connection| {
+        connections.fetch_add(1, Ordering::SeqCst);
+        async {
+            Ok::<_, hyper::Error>(service_fn(|_request| async {
+                Ok::<_, hyper::Error>(Response::new(Body::from(GET_SCOPES_RESPONSE)))
+            }))
+        }
+    });
+    let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+    let url = format!("http://{}/onvif/device_service", server.local_addr());
+    tokio::spawn(server);
+    url
+}
+
+fn pooled_client(c: &mut Criterion) {
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+    let connections = Arc::new(AtomicUsize::new(0));
+    let urls = runtime.block_on(async {
+        (0..DEVICES)
+            .map(|_| start_device(connections.clone()))
+            .collect::<Vec<String>>()
+    });
+    let action = get_action(DEVICE_WSDL, "GetScopes");
+    let message = to_envelope(&device::GetScopes {}).unwrap();
+    let content_type = format!("application/soap+xml; charset=utf-8; {};", action);
+
+    let mut group = c.benchmark_group(format!("{} devices x {} GetScopes", DEVICES, CALLS_PER_DEVICE));
+    group
+        .sample_size(10)
+        .sampling_mode(SamplingMode::Flat)
+        .warm_up_time(Duration::from_secs(1))
+        .measurement_time(Duration::from_secs(10));
+
+    let mut sweeps = 0;
+    connections.store(0, Ordering::SeqCst);
+    group.bench_function("fresh client per call", |b| {
+        b.iter(|| {
+            sweeps += 1;
+            runtime.block_on(async {
+                for url in urls.iter() {
+                    for _ in 0..CALLS_PER_DEVICE {
+                        let request = Request::post(url.as_str())
+                            .header("CONTENT-TYPE", &content_type)
+                            .body(message.clone().into())
+                            .unwrap();
+                        let response = hyper::Client::new().request(request).await.unwrap();
+                        hyper::body::to_bytes(response.into_body()).await.unwrap();
+                    }
+                }
+            })
+        })
+    });
+    println!(
+        "fresh client per call: {} connections per sweep",
+        connections.swap(0, Ordering::SeqCst) / sweeps
+    );
+
+    let http = HttpRequest::new(HttpClientConfig::default()).unwrap();
+    let mut sweeps = 0;
+    group.bench_function("pooled client", |b| {
+        b.iter(|| {
+            sweeps += 1;
+            runtime.block_on(async {
+                for url in urls.iter() {
+                    for _ in 0..CALLS_PER_DEVICE {
+                        http.post(url, &action, &message).await.unwrap();
+                    }
+                }
+            })
+        })
+    });
+    println!(
+        "pooled client: {} connections over {} sweeps",
+        connections.load(Ordering::SeqCst),
+        sweeps
+    );
+    group.finish();
+}
+
+criterion_group!(benches, pooled_client);
+criterion_main!(benches);
@@ -0,0 +1,7 @@
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate yaserde_derive;
+
+pub mod onvif;
+pub mod tokio_onvif;
@@ -1,21 +1,13 @@
-#[macro_use]
-extern crate log;
-#[macro_use]
-extern crate yaserde_derive;
+use my_onvif::tokio_onvif::util;
 
-use env_logger;
-
-mod tokio_onvif;
-use tokio_onvif::util;
-
-mod onvif;
-use onvif::device_info::OnvifQuery;
-use onvif::device_info::OnvifQueryImpl;
+use my_onvif::onvif::device_info::OnvifQuery;
+use my_onvif::onvif::device_info::OnvifQueryImpl;
 
 #[tokio::main]
 async fn main() {
     // let (discovery_rx, discover_tx, stop_tx) = 
     //     util::start_simple_onvif_discovery(std::time::Duration::from_secs(1));
+    let onvif_query = OnvifQueryImpl::new();
     loop {
         tokio::time::delay_for(std::time::Duration::from_secs(1)).await;
         // discover_tx.lock().await.send(()).await.unwrap();
@@ -25,7 +17,6 @@ async fn main() {
 
         for device_uri in devices.iter() {
             println!("Found device: {:?}", device_uri);
-            let onvif_query = OnvifQueryImpl {};
             match onvif_query.get_device_ip_and_mac_address(device_uri).await {
                 Ok((ip, mac)) => {
                     println!("Found device ip: {:?}", ip);
@@ -0,0 +1,1825 @@
+//
+// Typed ONVIF request and response messages.
+//
+// Requests are serialized with yaserde and wrapped in a SOAP envelope by
+// `to_envelope` (see onvif::soap).  Responses are deserialized from the whole
+// SOAP envelope by `parse_response`, using the per-operation envelope types
+// generated by the `soap_response!` macro below.  Only the parts of each message that this
+// crate uses are modelled; anything else in a response is ignored.
+//
+// yaserde_derive 0.3 puts its impls in named constants, which rustc reports
+// as non-local definitions; the lint is allowed in the modules holding derived
+// types.
+use super::soap::Envelope;
+use std::io::Write;
+use yaserde::{YaDeserialize, YaSerialize};
+
+/// Implemented by every response envelope type, giving access to the response
+/// element inside the SOAP body.
+pub trait ResponseEnvelope {
+    type Response;
+    fn into_response(self) -> Self::Response;
+}
+
+/// A repeated child element of a request.  yaserde names the items of a
+/// `Vec` of structs after the item type rather than the field, which is wrong
+/// wherever the element and its type differ (`tds:User` is a `tt:User`);
+/// `Repeated` writes every item under the field's own name instead.
+#[derive(Default, PartialEq, Debug)]
+pub struct Repeated<T>(pub Vec<T>);
+
+impl<T> From<Vec<T>> for Repeated<T> {
+    fn from(items: Vec<T>) -> Self {
+        Repeated(items)
+    }
+}
+
+impl<T: YaSerialize> YaSerialize for Repeated<T> {
+    fn serialize<W: Write>(&self, writer: &mut yaserde::ser::Serializer<W>) -> Result<(), String> {
+        let name = writer.get_start_event_name();
+        for item in &self.0 {
+            writer.set_start_event_name(name.clone());
+            writer.set_skip_start_end(false);
+            item.serialize(writer)?;
+        }
+        Ok(())
+    }
+}
+
+/// Serializes `request` into the body of a SOAP envelope.  String values are
+/// escaped by the serializer.
+pub fn to_envelope<T: YaSerialize>(request: &T) -> Result<String, failure::Error> {
+    // an inner serializer writes no XML declaration, and unlike
+    // to_string_content it keeps the request element itself
+    let mut serializer = yaserde::ser::Serializer::new_for_inner(Vec::new());
+    request
+        .serialize(&mut serializer)
+        .map_err(|e| failure::format_err!("failed to serialize request: {}", e))?;
+    let body = String::from_utf8(serializer.into_inner())?;
+    Ok(Envelope::new().body(&body).build())
+}
+
+/// Deserializes a SOAP envelope and returns the response element in its body.
+pub fn parse_response<E: ResponseEnvelope + YaDeserialize>(
+    response: &str,
+) -> Result<E::Response, failure::Error> {
+    match yaserde::de::from_str::<E>(response) {
+        Ok(envelope) => Ok(envelope.into_response()),
+        Err(e) => Err(failure::format_err!("failed to parse response: {}", e)),
+    }
+}
+
+//
+// Generates `$envelope` and `$body` types for a response, equivalent to:
+//
+//     <s:Envelope><s:Body><$element>...</$element></s:Body></s:Envelope>
+//
+// where the contents of $element are deserialized into `$response`.
+//
+macro_rules! soap_response {
+    ($envelope:ident, $body:ident, $element:tt, $field:ident: $response:ident, $prefix:tt, $namespace:tt) => {
+        #[derive(Default, PartialEq, Debug, YaDeserialize)]
+        #[yaserde(prefix = "s", namespace = "s: http://www.w3.org/2003/05/soap-envelope")]
+        pub struct $envelope {
+            #[yaserde(prefix = "s", rename = "Body")]
+            pub body: $body,
+        }
+
+        #[derive(Default, PartialEq, Debug, YaDeserialize)]
+        #[yaserde(
+            prefix = "s",
+            namespace = "s: http://www.w3.org/2003/05/soap-envelope",
+            namespace = $namespace
+        )]
+        pub struct $body {
+            #[yaserde(prefix = $prefix, rename = $element)]
+            pub $field: $response,
+        }
+
+        impl ResponseEnvelope for $envelope {
+            type Response = $response;
+            fn into_response(self) -> $response {
+                self.body.$field
+            }
+        }
+    };
+}
+
+/// Types from the ONVIF schema namespace (http://www.onvif.org/ver10/schema)
+/// shared by several services.  Types that appear as children of device or
+/// media service elements also declare that service's namespace.
+pub mod schema {
+    #![allow(non_local_definitions)]
+    use super::Repeated;
+    use std::io::{Read, Write};
+    use yaserde::{YaDeserialize, YaSerialize};
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tt",
+        namespace = "tt: http://www.onvif.org/ver10/schema",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl"
+    )]
+    pub struct NetworkInterface {
+        #[yaserde(attribute)]
+        pub token: String,
+        #[yaserde(prefix = "tt", rename = "Enabled")]
+        pub enabled: bool,
+        #[yaserde(prefix = "tt", rename = "Info")]
+        pub info: Option<NetworkInterfaceInfo>,
+        #[yaserde(prefix = "tt", rename = "Link")]
+        pub link: Option<NetworkInterfaceLink>,
+        #[yaserde(prefix = "tt", rename = "IPv4")]
+        pub ipv4: Option<IPv4NetworkInterface>,
+        #[yaserde(prefix = "tt", rename = "IPv6")]
+        pub ipv6: Option<IPv6NetworkInterface>,
+    }
+
+    #[derive(Clone, Copy, Default, PartialEq, Debug, YaSerialize, YaDeserialize)]
+    #[yaserde(prefix = "tt", namespace = "tt: http://www.onvif.org/ver10/schema")]
+    pub enum Duplex {
+        #[default]
+        Full,
+        Half,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaSerialize, YaDeserialize)]
+    #[yaserde(prefix = "tt", namespace = "tt: http://www.onvif.org/ver10/schema")]
+    pub struct NetworkInterfaceConnectionSetting {
+        #[yaserde(prefix = "tt", rename = "AutoNegotiation")]
+        pub auto_negotiation: bool,
+        #[yaserde(prefix = "tt", rename = "Speed")]
+        pub speed: i32,
+        #[yaserde(prefix = "tt", rename = "Duplex")]
+        pub duplex: Duplex,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(prefix = "tt", namespace = "tt: http://www.onvif.org/ver10/schema")]
+    pub struct NetworkInterfaceLink {
+        #[yaserde(prefix = "tt", rename = "AdminSettings")]
+        pub admin_settings: NetworkInterfaceConnectionSetting,
+        #[yaserde(prefix = "tt", rename = "OperSettings")]
+        pub oper_settings: Option<NetworkInterfaceConnectionSetting>,
+        /// IANA ifType, i.e. 6 for ethernet and 71 for 802.11.
+        #[yaserde(prefix = "tt", rename = "InterfaceType")]
+        pub interface_type: i32,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(prefix = "tt", namespace = "tt: http://www.onvif.org/ver10/schema")]
+    pub struct NetworkInterfaceInfo {
+        #[yaserde(prefix = "tt", rename = "Name")]
+        pub name: Option<String>,
+        #[yaserde(prefix = "tt", rename = "HwAddress")]
+        pub hw_address: String,
+        #[yaserde(prefix = "tt", rename = "MTU")]
+        pub mtu: Option<i32>,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(prefix = "tt", namespace = "tt: http://www.onvif.org/ver10/schema")]
+    pub struct IPv4NetworkInterface {
+        #[yaserde(prefix = "tt", rename = "Enabled")]
+        pub enabled: bool,
+        #[yaserde(prefix = "tt", rename = "Config")]
+        pub config: IPv4Configuration,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(prefix = "tt", namespace = "tt: http://www.onvif.org/ver10/schema")]
+    pub struct IPv4Configuration {
+        #[yaserde(prefix = "tt", rename = "Manual")]
+        pub manual: Vec<PrefixedIPv4Address>,
+        #[yaserde(prefix = "tt", rename = "LinkLocal")]
+        pub link_local: Option<PrefixedIPv4Address>,
+        #[yaserde(prefix = "tt", rename = "FromDHCP")]
+        pub from_dhcp: Option<PrefixedIPv4Address>,
+        #[yaserde(prefix = "tt", rename = "DHCP")]
+        pub dhcp: bool,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(prefix = "tt", namespace = "tt: http://www.onvif.org/ver10/schema")]
+    pub struct IPv6NetworkInterface {
+        #[yaserde(prefix = "tt", rename = "Enabled")]
+        pub enabled: bool,
+        #[yaserde(prefix = "tt", rename = "Config")]
+        pub config: Option<IPv6Configuration>,
+    }
+
+    #[derive(Clone, Copy, Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(prefix = "tt", namespace = "tt: http://www.onvif.org/ver10/schema")]
+    pub enum IPv6DHCPConfiguration {
+        Auto,
+        Stateful,
+        Stateless,
+        #[default]
+        Off,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(prefix = "tt", namespace = "tt: http://www.onvif.org/ver10/schema")]
+    pub struct IPv6Configuration {
+        #[yaserde(prefix = "tt", rename = "AcceptRouterAdvert")]
+        pub accept_router_advert: Option<bool>,
+        #[yaserde(prefix = "tt", rename = "DHCP")]
+        pub dhcp: IPv6DHCPConfiguration,
+        #[yaserde(prefix = "tt", rename = "Manual")]
+        pub manual: Vec<PrefixedIPv6Address>,
+        #[yaserde(prefix = "tt", rename = "LinkLocal")]
+        pub link_local: Vec<PrefixedIPv6Address>,
+        #[yaserde(prefix = "tt", rename = "FromDHCP")]
+        pub from_dhcp: Vec<PrefixedIPv6Address>,
+        #[yaserde(prefix = "tt", rename = "FromRA")]
+        pub from_ra: Vec<PrefixedIPv6Address>,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(prefix = "tt", namespace = "tt: http://www.onvif.org/ver10/schema")]
+    pub struct PrefixedIPv6Address {
+        #[yaserde(prefix = "tt", rename = "Address")]
+        pub address: String,
+        #[yaserde(prefix = "tt", rename = "PrefixLength")]
+        pub prefix_length: i32,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaSerialize, YaDeserialize)]
+    #[yaserde(prefix = "tt", namespace = "tt: http://www.onvif.org/ver10/schema")]
+    pub struct PrefixedIPv4Address {
+        #[yaserde(prefix = "tt", rename = "Address")]
+        pub address: String,
+        #[yaserde(prefix = "tt", rename = "PrefixLength")]
+        pub prefix_length: i32,
+    }
+
+    #[derive(Clone, Copy, Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(prefix = "tt", namespace = "tt: http://www.onvif.org/ver10/schema")]
+    pub enum ScopeDefinition {
+        #[default]
+        Fixed,
+        Configurable,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tt",
+        namespace = "tt: http://www.onvif.org/ver10/schema",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl"
+    )]
+    pub struct Scope {
+        #[yaserde(prefix = "tt", rename = "ScopeDef")]
+        pub scope_def: ScopeDefinition,
+        #[yaserde(prefix = "tt", rename = "ScopeItem")]
+        pub scope_item: String,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tt",
+        namespace = "tt: http://www.onvif.org/ver10/schema",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl"
+    )]
+    pub struct OnvifVersion {
+        #[yaserde(prefix = "tt", rename = "Major")]
+        pub major: i32,
+        #[yaserde(prefix = "tt", rename = "Minor")]
+        pub minor: i32,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tt",
+        namespace = "tt: http://www.onvif.org/ver10/schema",
+        namespace = "trt: http://www.onvif.org/ver10/media/wsdl"
+    )]
+    pub struct Profile {
+        #[yaserde(attribute)]
+        pub token: String,
+        #[yaserde(attribute)]
+        pub fixed: bool,
+        #[yaserde(prefix = "tt", rename = "Name")]
+        pub name: String,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(prefix = "tt", namespace = "tt: http://www.onvif.org/ver10/schema")]
+    pub enum StreamType {
+        #[yaserde(rename = "RTP-Unicast")]
+        #[default]
+        RtpUnicast,
+        #[yaserde(rename = "RTP-Multicast")]
+        RtpMulticast,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(prefix = "tt", namespace = "tt: http://www.onvif.org/ver10/schema")]
+    pub enum TransportProtocol {
+        UDP,
+        TCP,
+        #[default]
+        RTSP,
+        HTTP,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(prefix = "tt", namespace = "tt: http://www.onvif.org/ver10/schema")]
+    pub struct Transport {
+        #[yaserde(prefix = "tt", rename = "Protocol")]
+        pub protocol: TransportProtocol,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(
+        prefix = "trt",
+        namespace = "trt: http://www.onvif.org/ver10/media/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct StreamSetup {
+        #[yaserde(prefix = "tt", rename = "Stream")]
+        pub stream: StreamType,
+        #[yaserde(prefix = "tt", rename = "Transport")]
+        pub transport: Transport,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tt",
+        namespace = "tt: http://www.onvif.org/ver10/schema",
+        namespace = "trt: http://www.onvif.org/ver10/media/wsdl"
+    )]
+    pub struct MediaUri {
+        #[yaserde(prefix = "tt", rename = "Uri")]
+        pub uri: String,
+        #[yaserde(prefix = "tt", rename = "InvalidAfterConnect")]
+        pub invalid_after_connect: bool,
+        #[yaserde(prefix = "tt", rename = "InvalidAfterReboot")]
+        pub invalid_after_reboot: bool,
+        #[yaserde(prefix = "tt", rename = "Timeout")]
+        pub timeout: String,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(prefix = "tt", namespace = "tt: http://www.onvif.org/ver10/schema")]
+    pub enum CapabilityCategory {
+        #[default]
+        All,
+        Analytics,
+        Device,
+        Events,
+        Imaging,
+        Media,
+        PTZ,
+    }
+
+    /// The legacy (GetCapabilities) description of one service.  Only the
+    /// XAddr is common to every service's capabilities element.
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(prefix = "tt", namespace = "tt: http://www.onvif.org/ver10/schema")]
+    pub struct ServiceCapabilities {
+        #[yaserde(prefix = "tt", rename = "XAddr")]
+        pub xaddr: String,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tt",
+        namespace = "tt: http://www.onvif.org/ver10/schema",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl"
+    )]
+    pub struct Capabilities {
+        #[yaserde(prefix = "tt", rename = "Analytics")]
+        pub analytics: Option<ServiceCapabilities>,
+        #[yaserde(prefix = "tt", rename = "Device")]
+        pub device: Option<ServiceCapabilities>,
+        #[yaserde(prefix = "tt", rename = "Events")]
+        pub events: Option<ServiceCapabilities>,
+        #[yaserde(prefix = "tt", rename = "Imaging")]
+        pub imaging: Option<ServiceCapabilities>,
+        #[yaserde(prefix = "tt", rename = "Media")]
+        pub media: Option<ServiceCapabilities>,
+        #[yaserde(prefix = "tt", rename = "PTZ")]
+        pub ptz: Option<ServiceCapabilities>,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaSerialize, YaDeserialize)]
+    #[yaserde(prefix = "tt", namespace = "tt: http://www.onvif.org/ver10/schema")]
+    pub enum SetDateTimeType {
+        #[default]
+        Manual,
+        NTP,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaSerialize, YaDeserialize)]
+    #[yaserde(prefix = "tt", namespace = "tt: http://www.onvif.org/ver10/schema")]
+    pub struct Time {
+        #[yaserde(prefix = "tt", rename = "Hour")]
+        pub hour: i32,
+        #[yaserde(prefix = "tt", rename = "Minute")]
+        pub minute: i32,
+        #[yaserde(prefix = "tt", rename = "Second")]
+        pub second: i32,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaSerialize, YaDeserialize)]
+    #[yaserde(prefix = "tt", namespace = "tt: http://www.onvif.org/ver10/schema")]
+    pub struct Date {
+        #[yaserde(prefix = "tt", rename = "Year")]
+        pub year: i32,
+        #[yaserde(prefix = "tt", rename = "Month")]
+        pub month: i32,
+        #[yaserde(prefix = "tt", rename = "Day")]
+        pub day: i32,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaSerialize, YaDeserialize)]
+    #[yaserde(prefix = "tt", namespace = "tt: http://www.onvif.org/ver10/schema")]
+    pub struct DateTime {
+        #[yaserde(prefix = "tt", rename = "Time")]
+        pub time: Time,
+        #[yaserde(prefix = "tt", rename = "Date")]
+        pub date: Date,
+    }
+
+    impl DateTime {
+        /// Returns the date and time without a time zone, i.e. a device's local
+        /// time.  Returns `None` for values that are not a valid date and time.
+        pub fn to_naive(&self) -> Option<chrono::NaiveDateTime> {
+            let date = chrono::NaiveDate::from_ymd_opt(
+                self.date.year,
+                self.date.month as u32,
+                self.date.day as u32,
+            )?;
+            date.and_hms_opt(
+                self.time.hour as u32,
+                self.time.minute as u32,
+                self.time.second as u32,
+            )
+        }
+
+        /// Interprets the date and time as UTC.  Returns `None` for values that
+        /// are not a valid date and time.
+        pub fn to_utc(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+            let date_time = self.to_naive()?;
+            Some(chrono::TimeZone::from_utc_datetime(&chrono::Utc, &date_time))
+        }
+
+        pub fn from_utc(date_time: &chrono::DateTime<chrono::Utc>) -> Self {
+            use chrono::{Datelike, Timelike};
+            DateTime {
+                time: Time {
+                    hour: date_time.hour() as i32,
+                    minute: date_time.minute() as i32,
+                    second: date_time.second() as i32,
+                },
+                date: Date {
+                    year: date_time.year(),
+                    month: date_time.month() as i32,
+                    day: date_time.day() as i32,
+                },
+            }
+        }
+    }
+
+    #[derive(Default, PartialEq, Debug, YaSerialize, YaDeserialize)]
+    #[yaserde(prefix = "tt", namespace = "tt: http://www.onvif.org/ver10/schema")]
+    pub struct TimeZone {
+        /// POSIX 1003.1 time zone, i.e. `CST-8` or `EST5EDT,M3.2.0,M11.1.0`.
+        #[yaserde(prefix = "tt", rename = "TZ")]
+        pub tz: String,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tt",
+        namespace = "tt: http://www.onvif.org/ver10/schema",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl"
+    )]
+    pub struct SystemDateTime {
+        #[yaserde(prefix = "tt", rename = "DateTimeType")]
+        pub date_time_type: SetDateTimeType,
+        #[yaserde(prefix = "tt", rename = "DaylightSavings")]
+        pub daylight_savings: bool,
+        #[yaserde(prefix = "tt", rename = "TimeZone")]
+        pub time_zone: Option<TimeZone>,
+        #[yaserde(prefix = "tt", rename = "UTCDateTime")]
+        pub utc_date_time: Option<DateTime>,
+        #[yaserde(prefix = "tt", rename = "LocalDateTime")]
+        pub local_date_time: Option<DateTime>,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(prefix = "tt", namespace = "tt: http://www.onvif.org/ver10/schema")]
+    pub struct NetworkInterfaceSetConfiguration {
+        #[yaserde(prefix = "tt", rename = "Enabled")]
+        pub enabled: Option<bool>,
+        #[yaserde(prefix = "tt", rename = "Link")]
+        pub link: Option<NetworkInterfaceConnectionSetting>,
+        #[yaserde(prefix = "tt", rename = "MTU")]
+        pub mtu: Option<i32>,
+        #[yaserde(prefix = "tt", rename = "IPv4")]
+        pub ipv4: Option<IPv4NetworkInterfaceSetConfiguration>,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(prefix = "tt", namespace = "tt: http://www.onvif.org/ver10/schema")]
+    pub struct IPv4NetworkInterfaceSetConfiguration {
+        #[yaserde(prefix = "tt", rename = "Enabled")]
+        pub enabled: Option<bool>,
+        #[yaserde(prefix = "tt", rename = "Manual")]
+        pub manual: Repeated<PrefixedIPv4Address>,
+        #[yaserde(prefix = "tt", rename = "DHCP")]
+        pub dhcp: Option<bool>,
+    }
+
+    #[derive(Clone, Copy, Default, PartialEq, Debug, YaSerialize, YaDeserialize)]
+    #[yaserde(prefix = "tt", namespace = "tt: http://www.onvif.org/ver10/schema")]
+    pub enum IPType {
+        #[default]
+        IPv4,
+        IPv6,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaSerialize, YaDeserialize)]
+    #[yaserde(
+        prefix = "tt",
+        namespace = "tt: http://www.onvif.org/ver10/schema",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl"
+    )]
+    pub struct IPAddress {
+        #[yaserde(prefix = "tt", rename = "Type")]
+        pub address_type: IPType,
+        #[yaserde(prefix = "tt", rename = "IPv4Address")]
+        pub ipv4_address: Option<String>,
+        #[yaserde(prefix = "tt", rename = "IPv6Address")]
+        pub ipv6_address: Option<String>,
+    }
+
+    #[derive(Clone, Copy, Default, PartialEq, Debug, YaSerialize, YaDeserialize)]
+    #[yaserde(prefix = "tt", namespace = "tt: http://www.onvif.org/ver10/schema")]
+    pub enum NetworkHostType {
+        #[default]
+        IPv4,
+        IPv6,
+        DNS,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaSerialize, YaDeserialize)]
+    #[yaserde(
+        prefix = "tt",
+        namespace = "tt: http://www.onvif.org/ver10/schema",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl"
+    )]
+    pub struct NetworkHost {
+        #[yaserde(prefix = "tt", rename = "Type")]
+        pub host_type: NetworkHostType,
+        #[yaserde(prefix = "tt", rename = "IPv4Address")]
+        pub ipv4_address: Option<String>,
+        #[yaserde(prefix = "tt", rename = "IPv6Address")]
+        pub ipv6_address: Option<String>,
+        #[yaserde(prefix = "tt", rename = "DNSname")]
+        pub dns_name: Option<String>,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tt",
+        namespace = "tt: http://www.onvif.org/ver10/schema",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl"
+    )]
+    pub struct HostnameInformation {
+        #[yaserde(prefix = "tt", rename = "FromDHCP")]
+        pub from_dhcp: bool,
+        #[yaserde(prefix = "tt", rename = "Name")]
+        pub name: Option<String>,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tt",
+        namespace = "tt: http://www.onvif.org/ver10/schema",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl"
+    )]
+    pub struct DNSInformation {
+        #[yaserde(prefix = "tt", rename = "FromDHCP")]
+        pub from_dhcp: bool,
+        #[yaserde(prefix = "tt", rename = "SearchDomain")]
+        pub search_domain: Vec<String>,
+        #[yaserde(prefix = "tt", rename = "DNSFromDHCP")]
+        pub dns_from_dhcp: Vec<IPAddress>,
+        #[yaserde(prefix = "tt", rename = "DNSManual")]
+        pub dns_manual: Vec<IPAddress>,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tt",
+        namespace = "tt: http://www.onvif.org/ver10/schema",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl"
+    )]
+    pub struct NTPInformation {
+        #[yaserde(prefix = "tt", rename = "FromDHCP")]
+        pub from_dhcp: bool,
+        #[yaserde(prefix = "tt", rename = "NTPFromDHCP")]
+        pub ntp_from_dhcp: Vec<NetworkHost>,
+        #[yaserde(prefix = "tt", rename = "NTPManual")]
+        pub ntp_manual: Vec<NetworkHost>,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tt",
+        namespace = "tt: http://www.onvif.org/ver10/schema",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl"
+    )]
+    pub struct NetworkGateway {
+        #[yaserde(prefix = "tt", rename = "IPv4Address")]
+        pub ipv4_address: Vec<String>,
+        #[yaserde(prefix = "tt", rename = "IPv6Address")]
+        pub ipv6_address: Vec<String>,
+    }
+
+    #[derive(Clone, Copy, Default, PartialEq, Debug, YaSerialize, YaDeserialize)]
+    #[yaserde(prefix = "tt", namespace = "tt: http://www.onvif.org/ver10/schema")]
+    pub enum UserLevel {
+        Administrator,
+        Operator,
+        #[default]
+        User,
+        Anonymous,
+        Extended,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaSerialize, YaDeserialize)]
+    #[yaserde(
+        prefix = "tt",
+        namespace = "tt: http://www.onvif.org/ver10/schema",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl"
+    )]
+    pub struct User {
+        #[yaserde(prefix = "tt", rename = "Username")]
+        pub username: String,
+        #[yaserde(prefix = "tt", rename = "Password")]
+        pub password: Option<String>,
+        #[yaserde(prefix = "tt", rename = "UserLevel")]
+        pub user_level: UserLevel,
+    }
+
+    #[derive(Clone, Copy, Default, PartialEq, Debug, YaSerialize, YaDeserialize)]
+    #[yaserde(prefix = "tt", namespace = "tt: http://www.onvif.org/ver10/schema")]
+    pub enum FactoryDefaultType {
+        Hard,
+        #[default]
+        Soft,
+    }
+
+    #[derive(Clone, Copy, Default, PartialEq, Debug, YaSerialize, YaDeserialize)]
+    #[yaserde(prefix = "tt", namespace = "tt: http://www.onvif.org/ver10/schema")]
+    pub enum NetworkProtocolType {
+        #[default]
+        HTTP,
+        HTTPS,
+        RTSP,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaSerialize, YaDeserialize)]
+    #[yaserde(
+        prefix = "tt",
+        namespace = "tt: http://www.onvif.org/ver10/schema",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl"
+    )]
+    pub struct NetworkProtocol {
+        #[yaserde(prefix = "tt", rename = "Name")]
+        pub name: NetworkProtocolType,
+        #[yaserde(prefix = "tt", rename = "Enabled")]
+        pub enabled: bool,
+        #[yaserde(prefix = "tt", rename = "Port")]
+        pub port: Vec<i32>,
+    }
+
+    #[derive(Clone, Copy, Default, PartialEq, Debug, YaSerialize, YaDeserialize)]
+    #[yaserde(prefix = "tt", namespace = "tt: http://www.onvif.org/ver10/schema")]
+    pub enum DiscoveryMode {
+        #[default]
+        Discoverable,
+        NonDiscoverable,
+    }
+
+    #[derive(Clone, Copy, Default, PartialEq, Debug, YaSerialize, YaDeserialize)]
+    #[yaserde(prefix = "tt", namespace = "tt: http://www.onvif.org/ver10/schema")]
+    pub enum SystemLogType {
+        #[default]
+        System,
+        Access,
+    }
+
+    /// Content of a SystemLog or SupportInformation.  `binary` is base64;
+    /// MTOM attachments are inlined by the transport.
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tt",
+        namespace = "tt: http://www.onvif.org/ver10/schema",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl"
+    )]
+    pub struct AttachedContent {
+        #[yaserde(prefix = "tt", rename = "Binary")]
+        pub binary: Option<String>,
+        #[yaserde(prefix = "tt", rename = "String")]
+        pub string: Option<String>,
+    }
+}
+
+/// Device service (http://www.onvif.org/ver10/device/wsdl) messages.
+pub mod device {
+    #![allow(non_local_definitions)]
+    use super::schema::*;
+    use super::{Repeated, ResponseEnvelope};
+    use std::io::{Read, Write};
+    use yaserde::{YaDeserialize, YaSerialize};
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(prefix = "tds", namespace = "tds: http://www.onvif.org/ver10/device/wsdl")]
+    pub struct GetNetworkInterfaces {}
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct GetNetworkInterfacesResponse {
+        #[yaserde(prefix = "tds", rename = "NetworkInterfaces")]
+        pub network_interfaces: Vec<NetworkInterface>,
+    }
+
+    soap_response!(
+        GetNetworkInterfacesResponseEnvelope,
+        GetNetworkInterfacesResponseBody,
+        "GetNetworkInterfacesResponse",
+        get_network_interfaces_response: GetNetworkInterfacesResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(prefix = "tds", namespace = "tds: http://www.onvif.org/ver10/device/wsdl")]
+    pub struct GetDeviceInformation {}
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(prefix = "tds", namespace = "tds: http://www.onvif.org/ver10/device/wsdl")]
+    pub struct GetDeviceInformationResponse {
+        #[yaserde(prefix = "tds", rename = "Manufacturer")]
+        pub manufacturer: String,
+        #[yaserde(prefix = "tds", rename = "Model")]
+        pub model: String,
+        #[yaserde(prefix = "tds", rename = "FirmwareVersion")]
+        pub firmware_version: String,
+        #[yaserde(prefix = "tds", rename = "SerialNumber")]
+        pub serial_number: String,
+        #[yaserde(prefix = "tds", rename = "HardwareId")]
+        pub hardware_id: String,
+    }
+
+    soap_response!(
+        GetDeviceInformationResponseEnvelope,
+        GetDeviceInformationResponseBody,
+        "GetDeviceInformationResponse",
+        get_device_information_response: GetDeviceInformationResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(prefix = "tds", namespace = "tds: http://www.onvif.org/ver10/device/wsdl")]
+    pub struct GetScopes {}
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct GetScopesResponse {
+        #[yaserde(prefix = "tds", rename = "Scopes")]
+        pub scopes: Vec<Scope>,
+    }
+
+    soap_response!(
+        GetScopesResponseEnvelope,
+        GetScopesResponseBody,
+        "GetScopesResponse",
+        get_scopes_response: GetScopesResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(prefix = "tds", namespace = "tds: http://www.onvif.org/ver10/device/wsdl")]
+    pub struct GetServices {
+        #[yaserde(prefix = "tds", rename = "IncludeCapability")]
+        pub include_capability: bool,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct Service {
+        #[yaserde(prefix = "tds", rename = "Namespace")]
+        pub namespace: String,
+        #[yaserde(prefix = "tds", rename = "XAddr")]
+        pub xaddr: String,
+        #[yaserde(prefix = "tds", rename = "Version")]
+        pub version: OnvifVersion,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct GetServicesResponse {
+        #[yaserde(prefix = "tds", rename = "Service")]
+        pub services: Vec<Service>,
+    }
+
+    soap_response!(
+        GetServicesResponseEnvelope,
+        GetServicesResponseBody,
+        "GetServicesResponse",
+        get_services_response: GetServicesResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct GetCapabilities {
+        #[yaserde(prefix = "tds", rename = "Category")]
+        pub category: CapabilityCategory,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct GetCapabilitiesResponse {
+        #[yaserde(prefix = "tds", rename = "Capabilities")]
+        pub capabilities: Capabilities,
+    }
+
+    soap_response!(
+        GetCapabilitiesResponseEnvelope,
+        GetCapabilitiesResponseBody,
+        "GetCapabilitiesResponse",
+        get_capabilities_response: GetCapabilitiesResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(prefix = "tds", namespace = "tds: http://www.onvif.org/ver10/device/wsdl")]
+    pub struct GetSystemDateAndTime {}
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct GetSystemDateAndTimeResponse {
+        #[yaserde(prefix = "tds", rename = "SystemDateAndTime")]
+        pub system_date_and_time: SystemDateTime,
+    }
+
+    soap_response!(
+        GetSystemDateAndTimeResponseEnvelope,
+        GetSystemDateAndTimeResponseBody,
+        "GetSystemDateAndTimeResponse",
+        get_system_date_and_time_response: GetSystemDateAndTimeResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct SetSystemDateAndTime {
+        #[yaserde(prefix = "tds", rename = "DateTimeType")]
+        pub date_time_type: SetDateTimeType,
+        #[yaserde(prefix = "tds", rename = "DaylightSavings")]
+        pub daylight_savings: bool,
+        #[yaserde(prefix = "tds", rename = "TimeZone")]
+        pub time_zone: Option<TimeZone>,
+        #[yaserde(prefix = "tds", rename = "UTCDateTime")]
+        pub utc_date_time: Option<DateTime>,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct SetSystemDateAndTimeResponse {}
+
+    soap_response!(
+        SetSystemDateAndTimeResponseEnvelope,
+        SetSystemDateAndTimeResponseBody,
+        "SetSystemDateAndTimeResponse",
+        set_system_date_and_time_response: SetSystemDateAndTimeResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct GetHostname {}
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct GetHostnameResponse {
+        #[yaserde(prefix = "tds", rename = "HostnameInformation")]
+        pub hostname_information: HostnameInformation,
+    }
+
+    soap_response!(
+        GetHostnameResponseEnvelope,
+        GetHostnameResponseBody,
+        "GetHostnameResponse",
+        get_hostname_response: GetHostnameResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct SetHostname {
+        #[yaserde(prefix = "tds", rename = "Name")]
+        pub name: String,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct SetHostnameResponse {}
+
+    soap_response!(
+        SetHostnameResponseEnvelope,
+        SetHostnameResponseBody,
+        "SetHostnameResponse",
+        set_hostname_response: SetHostnameResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct SetHostnameFromDHCP {
+        #[yaserde(prefix = "tds", rename = "FromDHCP")]
+        pub from_dhcp: bool,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct SetHostnameFromDHCPResponse {
+        #[yaserde(prefix = "tds", rename = "RebootNeeded")]
+        pub reboot_needed: bool,
+    }
+
+    soap_response!(
+        SetHostnameFromDHCPResponseEnvelope,
+        SetHostnameFromDHCPResponseBody,
+        "SetHostnameFromDHCPResponse",
+        set_hostname_from_dhcp_response: SetHostnameFromDHCPResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct GetDNS {}
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct GetDNSResponse {
+        #[yaserde(prefix = "tds", rename = "DNSInformation")]
+        pub dns_information: DNSInformation,
+    }
+
+    soap_response!(
+        GetDNSResponseEnvelope,
+        GetDNSResponseBody,
+        "GetDNSResponse",
+        get_dns_response: GetDNSResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct SetDNS {
+        #[yaserde(prefix = "tds", rename = "FromDHCP")]
+        pub from_dhcp: bool,
+        #[yaserde(prefix = "tds", rename = "SearchDomain")]
+        pub search_domain: Vec<String>,
+        #[yaserde(prefix = "tds", rename = "DNSManual")]
+        pub dns_manual: Repeated<IPAddress>,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct SetDNSResponse {}
+
+    soap_response!(
+        SetDNSResponseEnvelope,
+        SetDNSResponseBody,
+        "SetDNSResponse",
+        set_dns_response: SetDNSResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct GetNTP {}
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct GetNTPResponse {
+        #[yaserde(prefix = "tds", rename = "NTPInformation")]
+        pub ntp_information: NTPInformation,
+    }
+
+    soap_response!(
+        GetNTPResponseEnvelope,
+        GetNTPResponseBody,
+        "GetNTPResponse",
+        get_ntp_response: GetNTPResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct SetNTP {
+        #[yaserde(prefix = "tds", rename = "FromDHCP")]
+        pub from_dhcp: bool,
+        #[yaserde(prefix = "tds", rename = "NTPManual")]
+        pub ntp_manual: Repeated<NetworkHost>,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct SetNTPResponse {}
+
+    soap_response!(
+        SetNTPResponseEnvelope,
+        SetNTPResponseBody,
+        "SetNTPResponse",
+        set_ntp_response: SetNTPResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct GetNetworkDefaultGateway {}
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct GetNetworkDefaultGatewayResponse {
+        #[yaserde(prefix = "tds", rename = "NetworkGateway")]
+        pub network_gateway: NetworkGateway,
+    }
+
+    soap_response!(
+        GetNetworkDefaultGatewayResponseEnvelope,
+        GetNetworkDefaultGatewayResponseBody,
+        "GetNetworkDefaultGatewayResponse",
+        get_network_default_gateway_response: GetNetworkDefaultGatewayResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct SetNetworkDefaultGateway {
+        #[yaserde(prefix = "tds", rename = "IPv4Address")]
+        pub ipv4_address: Vec<String>,
+        #[yaserde(prefix = "tds", rename = "IPv6Address")]
+        pub ipv6_address: Vec<String>,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct SetNetworkDefaultGatewayResponse {}
+
+    soap_response!(
+        SetNetworkDefaultGatewayResponseEnvelope,
+        SetNetworkDefaultGatewayResponseBody,
+        "SetNetworkDefaultGatewayResponse",
+        set_network_default_gateway_response: SetNetworkDefaultGatewayResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct SetNetworkInterfaces {
+        #[yaserde(prefix = "tds", rename = "InterfaceToken")]
+        pub interface_token: String,
+        #[yaserde(prefix = "tds", rename = "NetworkInterface")]
+        pub network_interface: NetworkInterfaceSetConfiguration,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct SetNetworkInterfacesResponse {
+        #[yaserde(prefix = "tds", rename = "RebootNeeded")]
+        pub reboot_needed: bool,
+    }
+
+    soap_response!(
+        SetNetworkInterfacesResponseEnvelope,
+        SetNetworkInterfacesResponseBody,
+        "SetNetworkInterfacesResponse",
+        set_network_interfaces_response: SetNetworkInterfacesResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct GetUsers {}
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct GetUsersResponse {
+        #[yaserde(prefix = "tds", rename = "User")]
+        pub user: Vec<User>,
+    }
+
+    soap_response!(
+        GetUsersResponseEnvelope,
+        GetUsersResponseBody,
+        "GetUsersResponse",
+        get_users_response: GetUsersResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct CreateUsers {
+        #[yaserde(prefix = "tds", rename = "User")]
+        pub user: Repeated<User>,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct CreateUsersResponse {}
+
+    soap_response!(
+        CreateUsersResponseEnvelope,
+        CreateUsersResponseBody,
+        "CreateUsersResponse",
+        create_users_response: CreateUsersResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct DeleteUsers {
+        #[yaserde(prefix = "tds", rename = "Username")]
+        pub username: Vec<String>,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct DeleteUsersResponse {}
+
+    soap_response!(
+        DeleteUsersResponseEnvelope,
+        DeleteUsersResponseBody,
+        "DeleteUsersResponse",
+        delete_users_response: DeleteUsersResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct SetUser {
+        #[yaserde(prefix = "tds", rename = "User")]
+        pub user: Repeated<User>,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct SetUserResponse {}
+
+    soap_response!(
+        SetUserResponseEnvelope,
+        SetUserResponseBody,
+        "SetUserResponse",
+        set_user_response: SetUserResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct SystemReboot {}
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct SystemRebootResponse {
+        #[yaserde(prefix = "tds", rename = "Message")]
+        pub message: String,
+    }
+
+    soap_response!(
+        SystemRebootResponseEnvelope,
+        SystemRebootResponseBody,
+        "SystemRebootResponse",
+        system_reboot_response: SystemRebootResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct SetSystemFactoryDefault {
+        #[yaserde(prefix = "tds", rename = "FactoryDefault")]
+        pub factory_default: FactoryDefaultType,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct SetSystemFactoryDefaultResponse {}
+
+    soap_response!(
+        SetSystemFactoryDefaultResponseEnvelope,
+        SetSystemFactoryDefaultResponseBody,
+        "SetSystemFactoryDefaultResponse",
+        set_system_factory_default_response: SetSystemFactoryDefaultResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct SetScopes {
+        #[yaserde(prefix = "tds", rename = "Scopes")]
+        pub scopes: Vec<String>,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct SetScopesResponse {}
+
+    soap_response!(
+        SetScopesResponseEnvelope,
+        SetScopesResponseBody,
+        "SetScopesResponse",
+        set_scopes_response: SetScopesResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct AddScopes {
+        #[yaserde(prefix = "tds", rename = "ScopeItem")]
+        pub scope_item: Vec<String>,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct AddScopesResponse {}
+
+    soap_response!(
+        AddScopesResponseEnvelope,
+        AddScopesResponseBody,
+        "AddScopesResponse",
+        add_scopes_response: AddScopesResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct RemoveScopes {
+        #[yaserde(prefix = "tds", rename = "ScopeItem")]
+        pub scope_item: Vec<String>,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct RemoveScopesResponse {
+        #[yaserde(prefix = "tds", rename = "ScopeItem")]
+        pub scope_item: Vec<String>,
+    }
+
+    soap_response!(
+        RemoveScopesResponseEnvelope,
+        RemoveScopesResponseBody,
+        "RemoveScopesResponse",
+        remove_scopes_response: RemoveScopesResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct GetNetworkProtocols {}
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct GetNetworkProtocolsResponse {
+        #[yaserde(prefix = "tds", rename = "NetworkProtocols")]
+        pub network_protocols: Vec<NetworkProtocol>,
+    }
+
+    soap_response!(
+        GetNetworkProtocolsResponseEnvelope,
+        GetNetworkProtocolsResponseBody,
+        "GetNetworkProtocolsResponse",
+        get_network_protocols_response: GetNetworkProtocolsResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct SetNetworkProtocols {
+        #[yaserde(prefix = "tds", rename = "NetworkProtocols")]
+        pub network_protocols: Repeated<NetworkProtocol>,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct SetNetworkProtocolsResponse {}
+
+    soap_response!(
+        SetNetworkProtocolsResponseEnvelope,
+        SetNetworkProtocolsResponseBody,
+        "SetNetworkProtocolsResponse",
+        set_network_protocols_response: SetNetworkProtocolsResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct GetDiscoveryMode {}
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct GetDiscoveryModeResponse {
+        #[yaserde(prefix = "tds", rename = "DiscoveryMode")]
+        pub discovery_mode: DiscoveryMode,
+    }
+
+    soap_response!(
+        GetDiscoveryModeResponseEnvelope,
+        GetDiscoveryModeResponseBody,
+        "GetDiscoveryModeResponse",
+        get_discovery_mode_response: GetDiscoveryModeResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct SetDiscoveryMode {
+        #[yaserde(prefix = "tds", rename = "DiscoveryMode")]
+        pub discovery_mode: DiscoveryMode,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct SetDiscoveryModeResponse {}
+
+    soap_response!(
+        SetDiscoveryModeResponseEnvelope,
+        SetDiscoveryModeResponseBody,
+        "SetDiscoveryModeResponse",
+        set_discovery_mode_response: SetDiscoveryModeResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct GetSystemLog {
+        #[yaserde(prefix = "tds", rename = "LogType")]
+        pub log_type: SystemLogType,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct GetSystemLogResponse {
+        #[yaserde(prefix = "tds", rename = "SystemLog")]
+        pub system_log: AttachedContent,
+    }
+
+    soap_response!(
+        GetSystemLogResponseEnvelope,
+        GetSystemLogResponseBody,
+        "GetSystemLogResponse",
+        get_system_log_response: GetSystemLogResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct GetSystemSupportInformation {}
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct GetSystemSupportInformationResponse {
+        #[yaserde(prefix = "tds", rename = "SupportInformation")]
+        pub support_information: AttachedContent,
+    }
+
+    soap_response!(
+        GetSystemSupportInformationResponseEnvelope,
+        GetSystemSupportInformationResponseBody,
+        "GetSystemSupportInformationResponse",
+        get_system_support_information_response: GetSystemSupportInformationResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct StartFirmwareUpgrade {}
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct StartFirmwareUpgradeResponse {
+        #[yaserde(prefix = "tds", rename = "UploadUri")]
+        pub upload_uri: String,
+        #[yaserde(prefix = "tds", rename = "UploadDelay")]
+        pub upload_delay: String,
+        #[yaserde(prefix = "tds", rename = "ExpectedDownTime")]
+        pub expected_down_time: String,
+    }
+
+    soap_response!(
+        StartFirmwareUpgradeResponseEnvelope,
+        StartFirmwareUpgradeResponseBody,
+        "StartFirmwareUpgradeResponse",
+        start_firmware_upgrade_response: StartFirmwareUpgradeResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct UpgradeSystemFirmware {
+        /// The firmware image, base64.
+        #[yaserde(prefix = "tds", rename = "Firmware")]
+        pub firmware: String,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "tds",
+        namespace = "tds: http://www.onvif.org/ver10/device/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct UpgradeSystemFirmwareResponse {
+        #[yaserde(prefix = "tds", rename = "Message")]
+        pub message: String,
+    }
+
+    soap_response!(
+        UpgradeSystemFirmwareResponseEnvelope,
+        UpgradeSystemFirmwareResponseBody,
+        "UpgradeSystemFirmwareResponse",
+        upgrade_system_firmware_response: UpgradeSystemFirmwareResponse,
+        "tds",
+        "tds: http://www.onvif.org/ver10/device/wsdl"
+    );
+}
+
+/// Media service (http://www.onvif.org/ver10/media/wsdl) messages.
+pub mod media {
+    #![allow(non_local_definitions)]
+    use super::schema::*;
+    use super::ResponseEnvelope;
+    use std::io::{Read, Write};
+    use yaserde::{YaDeserialize, YaSerialize};
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(prefix = "trt", namespace = "trt: http://www.onvif.org/ver10/media/wsdl")]
+    pub struct GetProfiles {}
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "trt",
+        namespace = "trt: http://www.onvif.org/ver10/media/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct GetProfilesResponse {
+        #[yaserde(prefix = "trt", rename = "Profiles")]
+        pub profiles: Vec<Profile>,
+    }
+
+    soap_response!(
+        GetProfilesResponseEnvelope,
+        GetProfilesResponseBody,
+        "GetProfilesResponse",
+        get_profiles_response: GetProfilesResponse,
+        "trt",
+        "trt: http://www.onvif.org/ver10/media/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(
+        prefix = "trt",
+        namespace = "trt: http://www.onvif.org/ver10/media/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct GetStreamUri {
+        #[yaserde(prefix = "trt", rename = "StreamSetup")]
+        pub stream_setup: StreamSetup,
+        #[yaserde(prefix = "trt", rename = "ProfileToken")]
+        pub profile_token: String,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "trt",
+        namespace = "trt: http://www.onvif.org/ver10/media/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct GetStreamUriResponse {
+        #[yaserde(prefix = "trt", rename = "MediaUri")]
+        pub media_uri: MediaUri,
+    }
+
+    soap_response!(
+        GetStreamUriResponseEnvelope,
+        GetStreamUriResponseBody,
+        "GetStreamUriResponse",
+        get_stream_uri_response: GetStreamUriResponse,
+        "trt",
+        "trt: http://www.onvif.org/ver10/media/wsdl"
+    );
+
+    #[derive(Default, PartialEq, Debug, YaSerialize)]
+    #[yaserde(prefix = "trt", namespace = "trt: http://www.onvif.org/ver10/media/wsdl")]
+    pub struct GetSnapshotUri {
+        #[yaserde(prefix = "trt", rename = "ProfileToken")]
+        pub profile_token: String,
+    }
+
+    #[derive(Default, PartialEq, Debug, YaDeserialize)]
+    #[yaserde(
+        prefix = "trt",
+        namespace = "trt: http://www.onvif.org/ver10/media/wsdl",
+        namespace = "tt: http://www.onvif.org/ver10/schema"
+    )]
+    pub struct GetSnapshotUriResponse {
+        #[yaserde(prefix = "trt", rename = "MediaUri")]
+        pub media_uri: MediaUri,
+    }
+
+    soap_response!(
+        GetSnapshotUriResponseEnvelope,
+        GetSnapshotUriResponseBody,
+        "GetSnapshotUriResponse",
+        get_snapshot_uri_response: GetSnapshotUriResponse,
+        "trt",
+        "trt: http://www.onvif.org/ver10/media/wsdl"
+    );
+}
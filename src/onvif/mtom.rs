@@ -0,0 +1,263 @@
+//
+// MTOM/XOP response decoding.
+//
+// Devices may answer operations returning binary data (GetSystemLog,
+// GetSystemSupportInformation, ...) with a `multipart/related` body: the SOAP
+// envelope is the root part and each binary value is a separate part,
+// referenced from the envelope with `<xop:Include href="cid:..."/>`.  The
+// attachments are arbitrary bytes, so such a body is not valid UTF-8.
+//
+// `decode` turns any response body into a single XML document: multipart
+// bodies are reassembled by replacing every xop:Include with the base64
+// encoding of the part it references, which is how the same value is sent
+// when MTOM is not used.  Other bodies are returned as they are.
+//
+use log::trace;
+
+/// Returns the XML document of a response body with content type
+/// `content_type`, inlining MTOM attachments as base64.
+pub fn decode(content_type: Option<&str>, body: &[u8]) -> Result<String, failure::Error> {
+    let content_type = match content_type {
+        Some(content_type) if is_multipart(content_type) => content_type,
+        _ => return Ok(std::str::from_utf8(body)?.to_string()),
+    };
+    let boundary = parameter(content_type, "boundary")
+        .ok_or_else(|| failure::format_err!("multipart response without boundary: {}", content_type))?;
+    let parts = split_parts(body, &boundary)?;
+    let root = match parameter(content_type, "start") {
+        Some(start) => parts
+            .iter()
+            .find(|part| part.content_id.as_deref() == Some(trim_angle_brackets(&start)))
+            .ok_or_else(|| failure::format_err!("multipart response without root part {}", start))?,
+        None => parts
+            .first()
+            .ok_or_else(|| failure::format_err!("multipart response without parts"))?,
+    };
+    trace!("decode - {} parts, root {:?}", parts.len(), root.content_id);
+    let document = std::str::from_utf8(root.content)
+        .map_err(|e| failure::format_err!("multipart root part is not UTF-8: {}", e))?;
+    inline_includes(document, &parts)
+}
+
+fn is_multipart(content_type: &str) -> bool {
+    content_type
+        .trim_start()
+        .to_ascii_lowercase()
+        .starts_with("multipart/related")
+}
+
+// Returns the value of parameter `name` of a header value such as
+// `multipart/related; boundary="uuid:1"; start="<root>"`.
+fn parameter(value: &str, name: &str) -> Option<String> {
+    value.split(';').skip(1).find_map(|parameter| {
+        let mut name_value = parameter.splitn(2, '=');
+        let parameter_name = name_value.next()?.trim();
+        let parameter_value = name_value.next()?.trim();
+        if parameter_name.eq_ignore_ascii_case(name) {
+            Some(parameter_value.trim_matches('"').to_string())
+        } else {
+            None
+        }
+    })
+}
+
+fn trim_angle_brackets(value: &str) -> &str {
+    value.trim().trim_start_matches('<').trim_end_matches('>')
+}
+
+struct Part<'a> {
+    content_id: Option<String>,
+    base64_encoded: bool,
+    content: &'a [u8],
+}
+
+fn split_parts<'a>(body: &'a [u8], boundary: &str) -> Result<Vec<Part<'a>>, failure::Error> {
+    let delimiter = format!("--{}", boundary).into_bytes();
+    let mut parts = Vec::new();
+    let mut position = find(body, &delimiter, 0)
+        .ok_or_else(|| failure::format_err!("multipart response without boundary {}", boundary))?;
+    loop {
+        position += delimiter.len();
+        // The closing delimiter is followed by `--`
+        if body[position..].starts_with(b"--") {
+            break;
+        }
+        let start = match find(body, b"\r\n", position) {
+            Some(line_end) => line_end + 2,
+            None => break,
+        };
+        let end = find(body, &[b"\r\n".as_ref(), &delimiter].concat(), start)
+            .ok_or_else(|| failure::format_err!("unterminated multipart part"))?;
+        parts.push(parse_part(&body[start..end])?);
+        position = end + 2;
+    }
+    Ok(parts)
+}
+
+fn parse_part(part: &[u8]) -> Result<Part<'_>, failure::Error> {
+    let headers_end = find(part, b"\r\n\r\n", 0)
+        .ok_or_else(|| failure::format_err!("multipart part without headers"))?;
+    let headers = String::from_utf8_lossy(&part[..headers_end]);
+    let mut content_id = None;
+    let mut base64_encoded = false;
+    for line in headers.split("\r\n") {
+        let mut name_value = line.splitn(2, ':');
+        let name = name_value.next().unwrap_or_default().trim();
+        let value = name_value.next().unwrap_or_default().trim();
+        if name.eq_ignore_ascii_case("Content-ID") {
+            content_id = Some(trim_angle_brackets(value).to_string());
+        } else if name.eq_ignore_ascii_case("Content-Transfer-Encoding") {
+            base64_encoded = value.eq_ignore_ascii_case("base64");
+        }
+    }
+    Ok(Part {
+        content_id,
+        base64_encoded,
+        content: &part[headers_end + 4..],
+    })
+}
+
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    if from > haystack.len() {
+        return None;
+    }
+    haystack[from..]
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|index| from + index)
+}
+
+// Replaces every `<prefix:Include href="cid:..."/>` with the base64 content
+// of the part it references.
+fn inline_includes(document: &str, parts: &[Part<'_>]) -> Result<String, failure::Error> {
+    let mut inlined = String::with_capacity(document.len());
+    let mut rest = document;
+    while let Some(start) = find_include(rest) {
+        let tag_end = rest[start..]
+            .find('>')
+            .map(|index| start + index)
+            .ok_or_else(|| failure::format_err!("unterminated xop:Include"))?;
+        let tag = &rest[start..=tag_end];
+        let element_end = if tag.ends_with("/>") {
+            tag_end + 1
+        } else {
+            let end_tag = format!("</{}>", qualified_name(&tag[1..]));
+            rest[tag_end..]
+                .find(&end_tag)
+                .map(|index| tag_end + index + end_tag.len())
+                .ok_or_else(|| failure::format_err!("unterminated xop:Include"))?
+        };
+        let content_id = href(tag)
+            .ok_or_else(|| failure::format_err!("xop:Include without cid href: {}", tag))?;
+        let part = parts
+            .iter()
+            .find(|part| part.content_id.as_deref() == Some(content_id.as_str()))
+            .ok_or_else(|| failure::format_err!("xop:Include of missing part {}", content_id))?;
+        inlined.push_str(&rest[..start]);
+        if part.base64_encoded {
+            inlined.extend(
+                String::from_utf8_lossy(part.content)
+                    .chars()
+                    .filter(|c| !c.is_whitespace()),
+            );
+        } else {
+            inlined.push_str(&base64::encode(part.content));
+        }
+        rest = &rest[element_end..];
+    }
+    inlined.push_str(rest);
+    Ok(inlined)
+}
+
+// Finds `<Include` or `<prefix:Include` followed by whitespace, `>` or `/`.
+fn find_include(text: &str) -> Option<usize> {
+    let mut offset = 0;
+    while let Some(index) = text[offset..].find('<') {
+        let start = offset + index;
+        let qualified_name = qualified_name(&text[start + 1..]);
+        let local_name = match qualified_name.rfind(':') {
+            Some(colon) => &qualified_name[colon + 1..],
+            None => qualified_name,
+        };
+        if local_name == "Include" {
+            return Some(start);
+        }
+        offset = start + 1;
+    }
+    None
+}
+
+fn qualified_name(tag: &str) -> &str {
+    let end = tag
+        .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .unwrap_or(tag.len());
+    &tag[..end]
+}
+
+// Returns the content id referenced by the href attribute of an xop:Include
+// start tag, percent-decoded as required for cid urls.
+fn href(tag: &str) -> Option<String> {
+    let start = tag.find("href=")? + "href=".len();
+    let quote = tag[start..].chars().next()?;
+    let value_start = start + 1;
+    let value_end = value_start + tag[value_start..].find(quote)?;
+    let value = &tag[value_start..value_end];
+    let content_id = value.trim_start_matches("cid:");
+    Some(percent_decode(content_id))
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' && index + 3 <= bytes.len() {
+            let byte = std::str::from_utf8(&bytes[index + 1..index + 3])
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            if let Some(byte) = byte {
+                decoded.push(byte);
+                index += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[index]);
+        index += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_inlines_binary_attachment() {
+        let attachment: &[u8] = &[0x1f, 0x8b, 0x08, 0x00, 0xff, 0xfe, 0x00, 0x0d, 0x0a];
+        let mut body = b"--uuid:boundary\r\nContent-Type: application/xop+xml; charset=UTF-8; type=\"application/soap+xml\"\r\nContent-Transfer-Encoding: binary\r\nContent-ID: <root@device>\r\n\r\n<SOAP-ENV:Envelope><SOAP-ENV:Body><tds:GetSystemLogResponse><tds:SystemLog><tt:Binary xmime:contentType=\"application/gzip\"><xop:Include xmlns:xop=\"http://www.w3.org/2004/08/xop/include\" href=\"cid:system%40device\"/></tt:Binary></tds:SystemLog></tds:GetSystemLogResponse></SOAP-ENV:Body></SOAP-ENV:Envelope>\r\n--uuid:boundary\r\nContent-Type: application/gzip\r\nContent-Transfer-Encoding: binary\r\nContent-ID: <system@device>\r\n\r\n".to_vec();
+        body.extend_from_slice(attachment);
+        body.extend_from_slice(b"\r\n--uuid:boundary--\r\n");
+
+        let document = decode(
+            Some("multipart/related; type=\"application/xop+xml\"; boundary=\"uuid:boundary\"; start=\"<root@device>\"; start-info=\"application/soap+xml\""),
+            &body,
+        )
+        .unwrap();
+        assert!(document.contains(&format!(
+            "<tt:Binary xmime:contentType=\"application/gzip\">{}</tt:Binary>",
+            base64::encode(attachment)
+        )));
+        assert!(!document.contains("Include"));
+    }
+
+    #[test]
+    fn test_decode_passes_other_bodies_through() {
+        let document = "<SOAP-ENV:Envelope><SOAP-ENV:Body/></SOAP-ENV:Envelope>";
+        assert_eq!(
+            document,
+            decode(Some("application/soap+xml; charset=utf-8"), document.as_bytes()).unwrap()
+        );
+        assert_eq!(document, decode(None, document.as_bytes()).unwrap());
+        assert!(decode(None, &[0xff, 0xfe]).is_err());
+    }
+}
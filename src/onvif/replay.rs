@@ -0,0 +1,311 @@
+//
+// Record-and-replay transport for offline tests.
+//
+// `Recorder` wraps a real transport and writes every exchange to a fixture
+// directory, one JSON file per distinct request.  `Replay` is a transport that
+// answers from such a directory, so every query can be run against a recorded
+// camera without the camera:
+//
+//     let http = HttpRequest::new(HttpClientConfig::default())?
+//         .with_recording("fixtures/vendor-x");
+//     ... run the queries against the real device ...
+//
+//     let onvif_query = OnvifQueryImpl::with_transport(Replay::open("fixtures/vendor-x")?);
+//
+// Fixtures are keyed by action and request body.  The SOAP header is left out
+// of the key (and of the stored request) since WS-Addressing and WS-Security
+// headers differ on every request, and the request is redacted like captured
+// exchanges are (see onvif::capture).  Responses are stored as received.
+// Uploads are not recorded and fail on replay.
+//
+use super::capture;
+use super::device_info::{Http, UploadProgress};
+use super::security::Credentials;
+use super::transport::{action_uri, operation_name, HttpStatusError};
+use async_trait::async_trait;
+use bytes::Bytes;
+use blake2::{Blake2b, Digest};
+use log::{trace, warn};
+use serde_derive::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+/// One recorded exchange.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Fixture {
+    pub action: String,
+    /// The url the exchange was recorded against, for reference only.
+    pub url: String,
+    pub request: String,
+    /// Http status of the response; faults are recorded with their status.
+    pub status: u16,
+    pub response: String,
+}
+
+impl Fixture {
+    pub fn key(&self) -> String {
+        fixture_key(&self.action, &self.request)
+    }
+}
+
+/// Returns the request as stored in a fixture: without SOAP header content
+/// and redacted.
+pub fn normalize_request(msg: &str) -> String {
+    capture::redact(&strip_header(msg))
+}
+
+fn strip_header(msg: &str) -> String {
+    let start = match msg.find("<soap:Header>") {
+        Some(start) => start,
+        None => return msg.to_string(),
+    };
+    match msg[start..].find("</soap:Header>") {
+        Some(end) => format!(
+            "{}<soap:Header/>{}",
+            &msg[..start],
+            &msg[start + end + "</soap:Header>".len()..]
+        ),
+        None => msg.to_string(),
+    }
+}
+
+/// Returns `<operation>-<hash of the request>`, which is also the fixture file
+/// name.  `request` must already be normalized.
+pub fn fixture_key(action: &str, request: &str) -> String {
+    let hash = Blake2b::digest(request.as_bytes())
+        .iter()
+        .take(8)
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+    format!("{}-{}", operation_name(action), hash)
+}
+
+/// Transport layer writing every exchange to a fixture directory.  Created
+/// with `HttpExt::with_recording`.
+pub struct Recorder<H> {
+    inner: H,
+    directory: PathBuf,
+}
+
+impl<H: Http> Recorder<H> {
+    pub fn new(inner: H, directory: PathBuf) -> Self {
+        Recorder { inner, directory }
+    }
+
+    fn record(&self, fixture: &Fixture) -> Result<(), failure::Error> {
+        std::fs::create_dir_all(&self.directory)?;
+        let path = self.directory.join(format!("{}.json", fixture.key()));
+        trace!("record - {} to {}", fixture.action, path.display());
+        std::fs::write(&path, serde_json::to_string_pretty(fixture)?).map_err(|e| {
+            failure::format_err!("failed to write fixture {}: {}", path.display(), e)
+        })
+    }
+}
+
+#[async_trait]
+impl<H: Http> Http for Recorder<H> {
+    async fn post(&self, url: &str, mime_action: &str, msg: &str) -> Result<String, failure::Error> {
+        let result = self.inner.post(url, mime_action, msg).await;
+        let (status, response) = match &result {
+            Ok(response) => (200, response.clone()),
+            Err(e) => match e.downcast_ref::<HttpStatusError>() {
+                Some(HttpStatusError { status, body }) => (*status, body.clone()),
+                // Nothing was received, so there is nothing to replay
+                None => return result,
+            },
+        };
+        // Recording is a side channel: the device already answered, so a
+        // fixture that cannot be written must not fail the caller's request
+        if let Err(e) = self.record(&Fixture {
+            action: action_uri(mime_action).to_string(),
+            url: capture::redact(url),
+            request: normalize_request(msg),
+            status,
+            response,
+        }) {
+            warn!("post - not recording {}: {}", action_uri(mime_action), e);
+        }
+        result
+    }
+
+    // Uploads are binary, so they are passed through without being recorded
+    async fn upload(
+        &self,
+        url: &str,
+        content_type: &str,
+        data: Bytes,
+        credentials: Option<&Credentials>,
+        progress: Option<UploadProgress>,
+    ) -> Result<String, failure::Error> {
+        self.inner.upload(url, content_type, data, credentials, progress).await
+    }
+}
+
+/// Transport answering requests from a fixture directory written by
+/// `Recorder`.  Requests without a fixture fail.
+#[derive(Debug)]
+pub struct Replay {
+    directory: PathBuf,
+    fixtures: HashMap<String, Fixture>,
+}
+
+impl Replay {
+    pub fn open<P: AsRef<Path>>(directory: P) -> Result<Self, failure::Error> {
+        let directory = directory.as_ref().to_path_buf();
+        let mut fixtures = HashMap::new();
+        let entries = std::fs::read_dir(&directory).map_err(|e| {
+            failure::format_err!("failed to read fixtures from {}: {}", directory.display(), e)
+        })?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+                continue;
+            }
+            let fixture: Fixture = serde_json::from_str(&std::fs::read_to_string(&path)?)
+                .map_err(|e| failure::format_err!("invalid fixture {}: {}", path.display(), e))?;
+            fixtures.insert(fixture.key(), fixture);
+        }
+        trace!("open - {} fixtures from {}", fixtures.len(), directory.display());
+        Ok(Replay {
+            directory,
+            fixtures,
+        })
+    }
+
+    pub fn fixtures(&self) -> impl Iterator<Item = &Fixture> {
+        self.fixtures.values()
+    }
+}
+
+#[async_trait]
+impl Http for Replay {
+    async fn post(&self, _url: &str, mime_action: &str, msg: &str) -> Result<String, failure::Error> {
+        let key = fixture_key(action_uri(mime_action), &normalize_request(msg));
+        match self.fixtures.get(&key) {
+            Some(fixture) if fixture.status == 200 => Ok(fixture.response.clone()),
+            Some(fixture) => Err(HttpStatusError {
+                status: fixture.status,
+                body: fixture.response.clone(),
+            }
+            .into()),
+            None => Err(failure::format_err!(
+                "no fixture {} in {}",
+                key,
+                self.directory.display()
+            )),
+        }
+    }
+
+    async fn upload(
+        &self,
+        url: &str,
+        _content_type: &str,
+        data: Bytes,
+        _credentials: Option<&Credentials>,
+        _progress: Option<UploadProgress>,
+    ) -> Result<String, failure::Error> {
+        Err(failure::format_err!(
+            "uploads are not recorded, cannot replay {} bytes to {}",
+            data.len(),
+            url
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::device_info::{
+        get_action, test_onvif::MockHttpImpl, OnvifQuery, OnvifQueryImpl, DEVICE_WSDL,
+    };
+    use super::super::messages::{device, to_envelope};
+    use super::super::soap;
+    use super::super::transport::HttpExt;
+    use super::*;
+
+    #[test]
+    fn test_normalize_request() {
+        let without_header = soap::Envelope::new().body("<tds:GetScopes/>").build();
+        let with_header = soap::Envelope::new()
+            .header("<wsa:MessageID xmlns:wsa=\"http://www.w3.org/2005/08/addressing\">uuid:1</wsa:MessageID>")
+            .body("<tds:GetScopes/>")
+            .build();
+        assert_eq!(without_header, normalize_request(&with_header));
+        assert_eq!(
+            fixture_key("http://www.onvif.org/ver10/device/wsdl/GetScopes", &without_header),
+            fixture_key("http://www.onvif.org/ver10/device/wsdl/GetScopes", &normalize_request(&with_header))
+        );
+        assert!(fixture_key("GetScopes", &without_header).starts_with("GetScopes-"));
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let directory = std::env::temp_dir().join(format!("onvif-fixtures-{}", uuid::Uuid::new_v4()));
+        let url = "http://192.168.1.35:8899/onvif/device_service";
+        let scopes_response = "<SOAP-ENV:Envelope xmlns:SOAP-ENV=\"http://www.w3.org/2003/05/soap-envelope\" xmlns:tt=\"http://www.onvif.org/ver10/schema\" xmlns:tds=\"http://www.onvif.org/ver10/device/wsdl\"><SOAP-ENV:Body><tds:GetScopesResponse><tds:Scopes><tt:ScopeDef>Fixed</tt:ScopeDef><tt:ScopeItem>onvif://www.onvif.org/name/NVT</tt:ScopeItem></tds:Scopes></tds:GetScopesResponse></SOAP-ENV:Body></SOAP-ENV:Envelope>";
+        let fault = "<SOAP-ENV:Envelope xmlns:SOAP-ENV=\"http://www.w3.org/2003/05/soap-envelope\"><SOAP-ENV:Body><SOAP-ENV:Fault/></SOAP-ENV:Body></SOAP-ENV:Envelope>";
+        {
+            let mut mock = MockHttpImpl::new();
+            mock.expect_post().times(2).returning(move |_, mime_action, _| {
+                if operation_name(mime_action) == "GetScopes" {
+                    Ok(scopes_response.to_string())
+                } else {
+                    Err(HttpStatusError {
+                        status: 500,
+                        body: fault.to_string(),
+                    }
+                    .into())
+                }
+            });
+            let onvif_query = OnvifQueryImpl::with_transport(mock.with_recording(directory.clone()));
+            assert!(onvif_query.get_device_scopes(url).await.is_ok());
+            assert!(onvif_query.get_device_ip_and_mac_address(url).await.is_err());
+        }
+
+        let replay = Replay::open(&directory).unwrap();
+        assert_eq!(2, replay.fixtures().count());
+        let onvif_query = OnvifQueryImpl::with_transport(replay);
+        // Replayed from another address
+        let replayed_url = "http://127.0.0.1:18899/onvif/device_service";
+        assert_eq!(
+            vec!["onvif://www.onvif.org/name/NVT".to_string()],
+            onvif_query.get_device_scopes(replayed_url).await.unwrap()
+        );
+        assert!(onvif_query.get_device_ip_and_mac_address(replayed_url).await.is_err());
+        assert!(onvif_query
+            .transport()
+            .post(
+                replayed_url,
+                &get_action(DEVICE_WSDL, "GetServices"),
+                &to_envelope(&device::GetServices { include_capability: false }).unwrap()
+            )
+            .await
+            .is_err());
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_recording_failure_does_not_fail_request() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        // A file where the fixture directory should be
+        let directory = std::env::temp_dir().join(format!("onvif-fixtures-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&directory, "").unwrap();
+        let mut mock = MockHttpImpl::new();
+        mock.expect_post()
+            .times(1)
+            .returning(|_, _, _| Ok("<response/>".to_string()));
+        let action = get_action(DEVICE_WSDL, "GetScopes");
+        assert_eq!(
+            "<response/>",
+            mock.with_recording(directory.clone())
+                .post("url", &action, "<msg/>")
+                .await
+                .unwrap()
+        );
+        std::fs::remove_file(&directory).unwrap();
+    }
+}
@@ -0,0 +1,545 @@
+//
+// WS-Security UsernameToken authentication.
+//
+// ONVIF devices authenticate requests with a password digest over a nonce and
+// a `Created` timestamp, and reject timestamps too far from their own clock.
+// Cameras without NTP drift, so the device clock is read with the
+// unauthenticated GetSystemDateAndTime operation on first contact and the
+// offset to the local clock is applied to every timestamp sent to that
+// device.  An authentication fault re-reads the device clock and, if it
+// moved, the request is sent once more.
+//
+// Uploads (i.e. firmware images) are not SOAP messages and cannot carry a
+// UsernameToken, so `WsSecurity` hands its credentials to the transport,
+// which answers the HTTP Basic or Digest challenge of the upload uri.
+//
+use super::device_info::{get_action, Http, UploadProgress, DEVICE_WSDL};
+use super::messages::{device, parse_response, to_envelope};
+use super::soap;
+use super::transport::{device_key, operation_name, HttpStatusError};
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{Duration, SecondsFormat, Utc};
+use log::{debug, info, trace};
+use rand::Rng;
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+pub const WSSE_NAMESPACE: &str =
+    "http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-wssecurity-secext-1.0.xsd";
+pub const WSU_NAMESPACE: &str =
+    "http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-wssecurity-utility-1.0.xsd";
+const PASSWORD_DIGEST_TYPE: &str = "http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-username-token-profile-1.0#PasswordDigest";
+const BASE64_ENCODING_TYPE: &str = "http://docs.oasis-open.org/wss/2004/01/oasis-200401-wss-soap-message-security-1.0#Base64Binary";
+
+/// Offsets smaller than this are not worth a log message.
+const NOTABLE_CLOCK_OFFSET_SECONDS: i64 = 5;
+
+#[derive(Clone, PartialEq)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+impl std::fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Credentials")
+            .field("username", &self.username)
+            .field("password", &"***")
+            .finish()
+    }
+}
+
+/// Difference between each device's clock and the local clock, keyed by the
+/// device host:port.  Shared by every `WsSecurity` layer talking to the same
+/// devices.
+#[derive(Debug, Default)]
+pub struct DeviceClocks {
+    offsets: Mutex<HashMap<String, Duration>>,
+}
+
+impl DeviceClocks {
+    pub fn new() -> Self {
+        DeviceClocks::default()
+    }
+
+    /// Device time minus local time for the device at `url`, if it was read.
+    pub fn offset(&self, url: &str) -> Option<Duration> {
+        self.offsets.lock().unwrap().get(&device_key(url)).cloned()
+    }
+
+    pub fn set_offset(&self, url: &str, offset: Duration) {
+        self.offsets.lock().unwrap().insert(device_key(url), offset);
+    }
+
+    /// Forgets the offset of the device at `url`, so that its clock is read
+    /// again before the next request.
+    pub fn forget(&self, url: &str) {
+        self.offsets.lock().unwrap().remove(&device_key(url));
+    }
+}
+
+/// Returns `base64(sha1(nonce + created + password))` as defined by the
+/// UsernameToken profile.
+pub fn password_digest(nonce: &[u8], created: &str, password: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.input(nonce);
+    hasher.input(created.as_bytes());
+    hasher.input(password.as_bytes());
+    base64::encode(hasher.result())
+}
+
+/// Builds the wsse:Security header for a request created at `created`.
+pub fn username_token(credentials: &Credentials, created: &str, nonce: &[u8]) -> String {
+    format!(
+        "<wsse:Security xmlns:wsse=\"{}\" xmlns:wsu=\"{}\" soap:mustUnderstand=\"1\">\
+         <wsse:UsernameToken>\
+         {}\
+         <wsse:Password Type=\"{}\">{}</wsse:Password>\
+         <wsse:Nonce EncodingType=\"{}\">{}</wsse:Nonce>\
+         {}\
+         </wsse:UsernameToken>\
+         </wsse:Security>",
+        WSSE_NAMESPACE,
+        WSU_NAMESPACE,
+        soap::element("wsse:Username", &credentials.username),
+        PASSWORD_DIGEST_TYPE,
+        password_digest(nonce, created, &credentials.password),
+        BASE64_ENCODING_TYPE,
+        base64::encode(nonce),
+        soap::element("wsu:Created", created)
+    )
+}
+
+/// Nonce count sent with Digest authorization; every nonce is used once.
+const DIGEST_NONCE_COUNT: &str = "00000001";
+
+/// Returns the Authorization header answering the `WWW-Authenticate`
+/// `challenges` of an HTTP server, preferring Digest (RFC 7616, MD5 or SHA-256
+/// with qop=auth) over Basic.  `uri` is the request target (path and query)
+/// and `cnonce` the client nonce to use with Digest.
+pub fn http_authorization(
+    credentials: &Credentials,
+    challenges: &[String],
+    method: &str,
+    uri: &str,
+    cnonce: &str,
+) -> Result<String, failure::Error> {
+    if let Some(challenge) = challenges
+        .iter()
+        .find_map(|challenge| challenge_parameters_of(challenge, "Digest"))
+    {
+        return digest_authorization(credentials, challenge, method, uri, cnonce);
+    }
+    if challenges
+        .iter()
+        .any(|challenge| challenge_parameters_of(challenge, "Basic").is_some())
+    {
+        return Ok(format!(
+            "Basic {}",
+            base64::encode(format!("{}:{}", credentials.username, credentials.password))
+        ));
+    }
+    Err(failure::format_err!(
+        "unsupported HTTP authentication: {:?}",
+        challenges
+    ))
+}
+
+/// Returns the parameters of `challenge` if it is for `scheme`.
+fn challenge_parameters_of<'a>(challenge: &'a str, scheme: &str) -> Option<&'a str> {
+    let challenge = challenge.trim();
+    let (name, parameters) = challenge.split_at(challenge.find(' ').unwrap_or(challenge.len()));
+    if name.eq_ignore_ascii_case(scheme) {
+        Some(parameters.trim())
+    } else {
+        None
+    }
+}
+
+/// Parses the `name=token` and `name="quoted string"` parameters of a
+/// challenge, keyed by lower case name.
+fn parse_challenge_parameters(parameters: &str) -> HashMap<String, String> {
+    let mut parsed = HashMap::new();
+    let mut rest = parameters.trim();
+    while let Some(equals) = rest.find('=') {
+        let name = rest[..equals].trim_matches(|c: char| c == ',' || c.is_whitespace());
+        rest = rest[equals + 1..].trim_start();
+        let value = if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            rest = quoted.get(end + 1..).unwrap_or_default();
+            &quoted[..end]
+        } else {
+            let end = rest.find(',').unwrap_or(rest.len());
+            let value = rest[..end].trim();
+            rest = &rest[end..];
+            value
+        };
+        parsed.insert(name.to_ascii_lowercase(), value.to_string());
+    }
+    parsed
+}
+
+fn digest_authorization(
+    credentials: &Credentials,
+    challenge: &str,
+    method: &str,
+    uri: &str,
+    cnonce: &str,
+) -> Result<String, failure::Error> {
+    let parameters = parse_challenge_parameters(challenge);
+    let nonce = match parameters.get("nonce") {
+        Some(nonce) => nonce,
+        None => return Err(failure::format_err!("digest challenge without a nonce")),
+    };
+    let realm = parameters.get("realm").map(String::as_str).unwrap_or_default();
+    let hash: fn(&str) -> String = match parameters.get("algorithm") {
+        None => |data| format!("{:x}", md5::compute(data)),
+        Some(algorithm) if algorithm.eq_ignore_ascii_case("MD5") => |data| format!("{:x}", md5::compute(data)),
+        Some(algorithm) if algorithm.eq_ignore_ascii_case("SHA-256") => {
+            |data| format!("{:x}", Sha256::digest(data.as_bytes()))
+        }
+        Some(algorithm) => {
+            return Err(failure::format_err!("unsupported digest algorithm {}", algorithm))
+        }
+    };
+    let ha1 = hash(&format!("{}:{}:{}", credentials.username, realm, credentials.password));
+    let ha2 = hash(&format!("{}:{}", method, uri));
+    let qop_auth = parameters
+        .get("qop")
+        .is_some_and(|qop| qop.split(',').any(|qop| qop.trim() == "auth"));
+    let mut authorization = format!(
+        "Digest username=\"{}\", realm=\"{}\", nonce=\"{}\", uri=\"{}\"",
+        credentials.username, realm, nonce, uri
+    );
+    if qop_auth {
+        let response = hash(&format!(
+            "{}:{}:{}:{}:auth:{}",
+            ha1, nonce, DIGEST_NONCE_COUNT, cnonce, ha2
+        ));
+        authorization += &format!(
+            ", qop=auth, nc={}, cnonce=\"{}\", response=\"{}\"",
+            DIGEST_NONCE_COUNT, cnonce, response
+        );
+    } else {
+        authorization += &format!(", response=\"{}\"", hash(&format!("{}:{}:{}", ha1, nonce, ha2)));
+    }
+    if let Some(algorithm) = parameters.get("algorithm") {
+        authorization += &format!(", algorithm={}", algorithm);
+    }
+    if let Some(opaque) = parameters.get("opaque") {
+        authorization += &format!(", opaque=\"{}\"", opaque);
+    }
+    Ok(authorization)
+}
+
+/// Whether `error` is a device rejecting a request's credentials.  Devices
+/// report this as 401 or as a SOAP fault, depending on the firmware.
+pub fn is_authentication_failure(error: &failure::Error) -> bool {
+    match error.downcast_ref::<HttpStatusError>() {
+        Some(HttpStatusError { status, body }) => {
+            *status == 401
+                || body.contains("NotAuthorized")
+                || body.contains("FailedAuthentication")
+                || body.contains("InvalidSecurity")
+        }
+        None => false,
+    }
+}
+
+/// Transport layer adding a UsernameToken to every request.  Created with
+/// `HttpExt::with_ws_security`.
+pub struct WsSecurity<H> {
+    inner: H,
+    credentials: Credentials,
+    clocks: Arc<DeviceClocks>,
+}
+
+impl<H: Http> WsSecurity<H> {
+    pub fn new(inner: H, credentials: Credentials, clocks: Arc<DeviceClocks>) -> Self {
+        WsSecurity {
+            inner,
+            credentials,
+            clocks,
+        }
+    }
+
+    /// Reads the clock of the device at `url` and records its offset.  Devices
+    /// that do not report their time are assumed to be in sync.
+    async fn sync_clock(&self, url: &str) -> Duration {
+        let offset = match self.device_time(url).await {
+            Ok(device_time) => device_time.signed_duration_since(Utc::now()),
+            Err(e) => {
+                debug!("sync_clock - failed to read clock of {}, using local time: {}", url, e);
+                Duration::zero()
+            }
+        };
+        if offset.num_seconds().abs() >= NOTABLE_CLOCK_OFFSET_SECONDS {
+            info!("sync_clock - clock of {} is off by {}s", url, offset.num_seconds());
+        }
+        self.clocks.set_offset(url, offset);
+        offset
+    }
+
+    async fn device_time(&self, url: &str) -> Result<chrono::DateTime<Utc>, failure::Error> {
+        let response = self
+            .inner
+            .post(
+                url,
+                &get_action(DEVICE_WSDL, "GetSystemDateAndTime"),
+                &to_envelope(&device::GetSystemDateAndTime {})?,
+            )
+            .await?;
+        let date_and_time =
+            parse_response::<device::GetSystemDateAndTimeResponseEnvelope>(&response)?
+                .system_date_and_time;
+        match date_and_time.utc_date_time.as_ref().and_then(|utc| utc.to_utc()) {
+            Some(device_time) => Ok(device_time),
+            None => Err(failure::format_err!("device did not report a valid UTC time")),
+        }
+    }
+
+    async fn post_authenticated(
+        &self,
+        url: &str,
+        mime_action: &str,
+        msg: &str,
+        offset: Duration,
+    ) -> Result<String, failure::Error> {
+        let created = (Utc::now() + offset).to_rfc3339_opts(SecondsFormat::Millis, true);
+        let nonce = rand::thread_rng().gen::<[u8; 16]>();
+        let msg = soap::insert_header(msg, &username_token(&self.credentials, &created, &nonce))?;
+        self.inner.post(url, mime_action, &msg).await
+    }
+}
+
+#[async_trait]
+impl<H: Http> Http for WsSecurity<H> {
+    async fn post(&self, url: &str, mime_action: &str, msg: &str) -> Result<String, failure::Error> {
+        // GetSystemDateAndTime is always allowed without credentials; it is how
+        // the clock offset is found in the first place.
+        if operation_name(mime_action) == "GetSystemDateAndTime" {
+            return self.inner.post(url, mime_action, msg).await;
+        }
+        let offset = match self.clocks.offset(url) {
+            Some(offset) => offset,
+            None => self.sync_clock(url).await,
+        };
+        match self.post_authenticated(url, mime_action, msg, offset).await {
+            Err(e) if is_authentication_failure(&e) => {
+                let resynced = self.sync_clock(url).await;
+                if (resynced - offset).num_seconds().abs() < 1 {
+                    // The clock was right, so the credentials are wrong
+                    trace!("post - {} rejected credentials with an accurate clock", url);
+                    return Err(e);
+                }
+                debug!("post - {} rejected credentials after clock drift, retrying", url);
+                self.post_authenticated(url, mime_action, msg, resynced).await
+            }
+            result => result,
+        }
+    }
+
+    // Uploads are not SOAP messages, so they are authenticated over HTTP
+    async fn upload(
+        &self,
+        url: &str,
+        content_type: &str,
+        data: Bytes,
+        credentials: Option<&Credentials>,
+        progress: Option<UploadProgress>,
+    ) -> Result<String, failure::Error> {
+        let credentials = credentials.unwrap_or(&self.credentials);
+        self.inner.upload(url, content_type, data, Some(credentials), progress).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::device_info::test_onvif::MockHttpImpl;
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    fn credentials() -> Credentials {
+        Credentials {
+            username: "admin".to_string(),
+            password: "userpassword".to_string(),
+        }
+    }
+
+    fn system_date_and_time_response(time: &chrono::DateTime<Utc>) -> String {
+        use chrono::{Datelike, Timelike};
+        format!(
+            "<SOAP-ENV:Envelope xmlns:SOAP-ENV=\"http://www.w3.org/2003/05/soap-envelope\" xmlns:tt=\"http://www.onvif.org/ver10/schema\" xmlns:tds=\"http://www.onvif.org/ver10/device/wsdl\"><SOAP-ENV:Body><tds:GetSystemDateAndTimeResponse><tds:SystemDateAndTime><tt:DateTimeType>Manual</tt:DateTimeType><tt:DaylightSavings>false</tt:DaylightSavings><tt:UTCDateTime><tt:Time><tt:Hour>{}</tt:Hour><tt:Minute>{}</tt:Minute><tt:Second>{}</tt:Second></tt:Time><tt:Date><tt:Year>{}</tt:Year><tt:Month>{}</tt:Month><tt:Day>{}</tt:Day></tt:Date></tt:UTCDateTime></tds:SystemDateAndTime></tds:GetSystemDateAndTimeResponse></SOAP-ENV:Body></SOAP-ENV:Envelope>",
+            time.hour(), time.minute(), time.second(), time.year(), time.month(), time.day()
+        )
+    }
+
+    fn created_offset(msg: &str) -> Duration {
+        let created = msg
+            .split("<wsu:Created>")
+            .nth(1)
+            .and_then(|rest| rest.split("</wsu:Created>").next())
+            .unwrap();
+        chrono::DateTime::parse_from_rfc3339(created)
+            .unwrap()
+            .signed_duration_since(Utc::now())
+    }
+
+    fn password_digest_of(msg: &str) -> String {
+        msg.split("<wsse:Password")
+            .nth(1)
+            .and_then(|rest| rest.split("</wsse:Password>").next())
+            .and_then(|element| element.split('>').nth(1))
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_password_digest() {
+        // Example from the ONVIF Application Programmer's Guide
+        let nonce = base64::decode("LKqI6G/AikKCQrN0zqZFlg==").unwrap();
+        assert_eq!(
+            "tuOSpGlFlIXsozq4HFNeeGeFLEI=",
+            password_digest(&nonce, "2010-09-16T07:50:45Z", "userpassword")
+        );
+        let token = username_token(&credentials(), "2010-09-16T07:50:45Z", &nonce);
+        assert!(token.contains("tuOSpGlFlIXsozq4HFNeeGeFLEI="));
+        assert!(!token.contains("userpassword"));
+        assert!(!format!("{:?}", credentials()).contains("userpassword"));
+    }
+
+    #[test]
+    fn test_http_authorization() {
+        // RFC 2617, section 3.5
+        let credentials = Credentials {
+            username: "Mufasa".to_string(),
+            password: "Circle Of Life".to_string(),
+        };
+        let digest = http_authorization(
+            &credentials,
+            &[
+                "Basic realm=\"testrealm@host.com\"".to_string(),
+                "Digest realm=\"testrealm@host.com\", qop=\"auth,auth-int\", nonce=\"dcd98b7102dd2f0e8b11d0f600bfb0c093\", opaque=\"5ccc069c403ebaf9f0171e9517f40e41\"".to_string(),
+            ],
+            "GET",
+            "/dir/index.html",
+            "0a4f113b",
+        )
+        .unwrap();
+        assert!(digest.starts_with("Digest username=\"Mufasa\""));
+        assert!(digest.contains("nc=00000001, cnonce=\"0a4f113b\", response=\"6629fae49393a05397450978507c4ef1\""));
+        assert!(digest.contains("opaque=\"5ccc069c403ebaf9f0171e9517f40e41\""));
+
+        assert_eq!(
+            "Basic TXVmYXNhOkNpcmNsZSBPZiBMaWZl",
+            http_authorization(&credentials, &["Basic realm=\"upload\"".to_string()], "POST", "/", "").unwrap()
+        );
+        assert!(http_authorization(&credentials, &["Negotiate".to_string()], "POST", "/", "").is_err());
+        assert!(http_authorization(
+            &credentials,
+            &["Digest realm=\"upload\", nonce=\"abc\", algorithm=MD5-sess".to_string()],
+            "POST",
+            "/",
+            ""
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_ws_security_applies_device_clock_offset() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let url = "http://192.168.1.35/onvif/device_service";
+        let clock_reads = Arc::new(AtomicUsize::new(0));
+        let clock_set_right = Arc::new(AtomicBool::new(false));
+        let digests = Arc::new(Mutex::new(Vec::new()));
+        let reads = clock_reads.clone();
+        let set_right = clock_set_right.clone();
+        let sent_digests = digests.clone();
+        let mut mock = MockHttpImpl::new();
+        mock.expect_post().returning(move |_, mime_action, msg| {
+            // Two hours ahead at first, then set right
+            let device_offset = match set_right.load(Ordering::SeqCst) {
+                false => Duration::hours(2),
+                true => Duration::zero(),
+            };
+            if operation_name(mime_action) == "GetSystemDateAndTime" {
+                assert!(!msg.contains("wsse:Security"));
+                reads.fetch_add(1, Ordering::SeqCst);
+                return Ok(system_date_and_time_response(&(Utc::now() + device_offset)));
+            }
+            sent_digests.lock().unwrap().push(password_digest_of(msg));
+            let offset = created_offset(msg);
+            if (offset - device_offset).num_seconds().abs() > 2 {
+                Err(HttpStatusError {
+                    status: 400,
+                    body: "<SOAP-ENV:Fault><SOAP-ENV:Subcode><SOAP-ENV:Value>ter:NotAuthorized</SOAP-ENV:Value></SOAP-ENV:Subcode></SOAP-ENV:Fault>".to_string(),
+                }
+                .into())
+            } else if device_offset == Duration::zero() {
+                Ok("<response after resync/>".to_string())
+            } else {
+                Ok("<response/>".to_string())
+            }
+        });
+        let clocks = Arc::new(DeviceClocks::new());
+        let http = WsSecurity::new(mock, credentials(), clocks.clone());
+
+        let action = get_action(DEVICE_WSDL, "GetScopes");
+        let msg = soap::Envelope::new().body("<tds:GetScopes/>").build();
+        assert_eq!("<response/>", http.post(url, &action, &msg).await.unwrap());
+        assert_eq!("<response/>", http.post(url, &action, &msg).await.unwrap());
+        assert_eq!(1, clock_reads.load(Ordering::SeqCst));
+        assert!((clocks.offset(url).unwrap() - Duration::hours(2)).num_seconds().abs() <= 2);
+
+        // The device clock is set right: the request with the old offset is
+        // rejected, the clock is read again and the request is sent again
+        clock_set_right.store(true, Ordering::SeqCst);
+        assert_eq!(
+            "<response after resync/>",
+            http.post(url, &action, &msg).await.unwrap()
+        );
+        assert_eq!(2, clock_reads.load(Ordering::SeqCst));
+        assert!(clocks.offset(url).unwrap().num_seconds().abs() <= 2);
+        let digests = digests.lock().unwrap();
+        assert_eq!(4, digests.len());
+        assert_ne!(digests[2], digests[3]);
+    }
+
+    #[tokio::test]
+    async fn test_ws_security_resyncs_after_authentication_fault() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let url = "http://192.168.1.35/onvif/device_service";
+        let mut mock = MockHttpImpl::new();
+        mock.expect_post().returning(move |_, mime_action, msg| {
+            if operation_name(mime_action) == "GetSystemDateAndTime" {
+                return Ok(system_date_and_time_response(&(Utc::now() + Duration::minutes(10))));
+            }
+            if (created_offset(msg) - Duration::minutes(10)).num_seconds().abs() <= 2 {
+                Ok("<response/>".to_string())
+            } else {
+                Err(HttpStatusError {
+                    status: 400,
+                    body: "<SOAP-ENV:Fault><SOAP-ENV:Subcode><SOAP-ENV:Value>ter:NotAuthorized</SOAP-ENV:Value></SOAP-ENV:Subcode></SOAP-ENV:Fault>".to_string(),
+                }
+                .into())
+            }
+        });
+        // A stale offset from before the device clock drifted
+        let clocks = Arc::new(DeviceClocks::new());
+        clocks.set_offset(url, Duration::zero());
+        let http = WsSecurity::new(mock, credentials(), clocks.clone());
+
+        let action = get_action(DEVICE_WSDL, "GetScopes");
+        let msg = soap::Envelope::new().body("<tds:GetScopes/>").build();
+        assert_eq!("<response/>", http.post(url, &action, &msg).await.unwrap());
+        assert!((clocks.offset(url).unwrap() - Duration::minutes(10)).num_seconds().abs() <= 2);
+    }
+}
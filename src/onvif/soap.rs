@@ -0,0 +1,462 @@
+//
+// SOAP envelope construction shared by every operation.
+//
+// All requests go through `Envelope`, which declares the SOAP 1.2 and ONVIF
+// namespaces with the same prefixes everywhere, escapes every value it is
+// given and produces a header section that transport layers can extend with
+// `insert_header` (for WS-Addressing and WS-Security headers).
+//
+use failure::Fail;
+
+pub const SOAP_ENVELOPE_NAMESPACE: &str = "http://www.w3.org/2003/05/soap-envelope";
+
+/// Envelope namespace of SOAP 1.1, still the only version some older encoders
+/// understand.
+pub const SOAP11_ENVELOPE_NAMESPACE: &str = "http://schemas.xmlsoap.org/soap/envelope/";
+
+/// SOAP version used to talk to a device.  Messages are always built as SOAP
+/// 1.2; the transport converts them for devices that only speak SOAP 1.1.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Hash)]
+pub enum SoapVersion {
+    Soap11,
+    #[default]
+    Soap12,
+}
+
+
+/// Namespaces declared on every envelope, usable by hand-written body and
+/// header elements.
+pub const STANDARD_NAMESPACES: &[(&str, &str)] = &[
+    ("soap", SOAP_ENVELOPE_NAMESPACE),
+    ("tt", "http://www.onvif.org/ver10/schema"),
+    ("tds", "http://www.onvif.org/ver10/device/wsdl"),
+    ("trt", "http://www.onvif.org/ver10/media/wsdl"),
+];
+
+const HEADER_START: &str = "<soap:Header>";
+const HEADER_END: &str = "</soap:Header>";
+const EMPTY_HEADER: &str = "<soap:Header/>";
+
+/// Escapes `value` for use as XML character data or attribute value.
+pub fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Returns `<name>value</name>` with `value` escaped.  `name` is used as is and
+/// should be a prefixed name such as `tds:ProfileToken`.
+pub fn element(name: &str, value: &str) -> String {
+    format!("<{}>{}</{}>", name, escape(value), name)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Envelope {
+    namespaces: Vec<(String, String)>,
+    headers: Vec<String>,
+    body: String,
+}
+
+impl Default for Envelope {
+    fn default() -> Self {
+        Envelope::new()
+    }
+}
+
+impl Envelope {
+    pub fn new() -> Self {
+        Envelope {
+            namespaces: STANDARD_NAMESPACES
+                .iter()
+                .map(|(prefix, namespace)| (prefix.to_string(), namespace.to_string()))
+                .collect(),
+            headers: Vec::new(),
+            body: String::new(),
+        }
+    }
+
+    /// Declares an additional namespace on the envelope.  Declaring a prefix
+    /// again replaces its namespace.
+    pub fn namespace(mut self, prefix: &str, namespace: &str) -> Self {
+        match self.namespaces.iter_mut().find(|(p, _)| p == prefix) {
+            Some(declared) => declared.1 = namespace.to_string(),
+            None => self
+                .namespaces
+                .push((prefix.to_string(), namespace.to_string())),
+        }
+        self
+    }
+
+    /// Appends a header block.  `header` must be well formed XML; build it with
+    /// `element` or `escape` when it contains caller-supplied values.
+    pub fn header(mut self, header: &str) -> Self {
+        self.headers.push(header.to_string());
+        self
+    }
+
+    /// Appends a body element.  `body` must be well formed XML; build it with
+    /// `element` or `escape` when it contains caller-supplied values.
+    pub fn body(mut self, body: &str) -> Self {
+        self.body.push_str(body);
+        self
+    }
+
+    pub fn build(&self) -> String {
+        let namespaces = self
+            .namespaces
+            .iter()
+            .map(|(prefix, namespace)| format!(" xmlns:{}=\"{}\"", prefix, escape(namespace)))
+            .collect::<String>();
+        let header = if self.headers.is_empty() {
+            EMPTY_HEADER.to_string()
+        } else {
+            format!("{}{}{}", HEADER_START, self.headers.concat(), HEADER_END)
+        };
+        format!(
+            "<soap:Envelope{}>{}<soap:Body>{}</soap:Body></soap:Envelope>",
+            namespaces, header, self.body
+        )
+    }
+}
+
+/// Adds a header block to an envelope built by `Envelope::build`.  Used by
+/// transport layers that add headers to requests they did not build.
+pub fn insert_header(message: &str, header: &str) -> Result<String, failure::Error> {
+    if let Some(index) = message.find(EMPTY_HEADER) {
+        Ok(format!(
+            "{}{}{}{}{}",
+            &message[..index],
+            HEADER_START,
+            header,
+            HEADER_END,
+            &message[index + EMPTY_HEADER.len()..]
+        ))
+    } else if let Some(index) = message.find(HEADER_END) {
+        Ok(format!("{}{}{}", &message[..index], header, &message[index..]))
+    } else {
+        Err(failure::format_err!(
+            "failed to add header: message has no soap:Header section"
+        ))
+    }
+}
+
+/// Returns a new unique message identifier, as used for WS-Discovery probes
+/// and WS-Addressing MessageID headers.
+pub fn new_message_id() -> String {
+    format!("uuid:{}", uuid::Uuid::new_v4())
+}
+
+/// Returns the text of the first header block named `local_name` (in any
+/// namespace) in `message`, or `None` when the message has no such header or
+/// is not well formed.
+pub fn header_value(message: &str, local_name: &str) -> Option<String> {
+    let package = sxd_document::parser::parse(message).ok()?;
+    let document = package.as_document();
+    let xpath = format!(
+        "string(/*[local-name()='Envelope']/*[local-name()='Header']/*[local-name()='{}'])",
+        local_name
+    );
+    match sxd_xpath::evaluate_xpath(&document, &xpath) {
+        Ok(sxd_xpath::Value::String(value)) if !value.trim().is_empty() => {
+            Some(value.trim().to_string())
+        }
+        _ => None,
+    }
+}
+
+/// A SOAP fault returned by a device.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SoapFault {
+    /// i.e. `env:Sender`
+    pub code: String,
+    /// i.e. `ter:InvalidArgVal`, empty when the fault has no subcode.
+    pub subcode: String,
+    pub reason: String,
+}
+
+impl std::fmt::Display for SoapFault {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "device returned SOAP fault {} ({}): {}", self.code, self.subcode, self.reason)
+    }
+}
+
+impl Fail for SoapFault {}
+
+/// A parsed response envelope, for operations without typed messages in
+/// onvif::messages.
+pub struct SoapResponse {
+    package: sxd_document::Package,
+}
+
+impl SoapResponse {
+    /// Parses `response`, returning the fault it carries as an error.
+    pub fn parse(response: &str) -> Result<Self, failure::Error> {
+        let package = sxd_document::parser::parse(response)
+            .map_err(|e| failure::format_err!("failed to parse response: {:?}", e))?;
+        let response = SoapResponse { package };
+        match response.fault() {
+            Some(fault) => Err(fault.into()),
+            None => Ok(response),
+        }
+    }
+
+    pub fn document(&self) -> sxd_document::dom::Document<'_> {
+        self.package.as_document()
+    }
+
+    /// Evaluates `xpath` against the response and returns its string value,
+    /// which is empty when nothing matches.  The prefixes of
+    /// `STANDARD_NAMESPACES` can be used in `xpath`; elements in other
+    /// namespaces can be matched with `local-name()`.
+    pub fn xpath(&self, xpath: &str) -> Result<String, failure::Error> {
+        Ok(self.evaluate(xpath)?.string())
+    }
+
+    /// Evaluates `xpath` against the response and returns the string value of
+    /// every matching node, in document order.
+    pub fn xpath_all(&self, xpath: &str) -> Result<Vec<String>, failure::Error> {
+        match self.evaluate(xpath)? {
+            sxd_xpath::Value::Nodeset(nodes) => Ok(nodes
+                .document_order()
+                .iter()
+                .map(|node| node.string_value())
+                .collect()),
+            value => Ok(vec![value.string()]),
+        }
+    }
+
+    fn evaluate(&self, xpath: &str) -> Result<sxd_xpath::Value<'_>, failure::Error> {
+        let compiled = match sxd_xpath::Factory::new().build(xpath) {
+            Ok(Some(compiled)) => compiled,
+            Ok(None) => return Err(failure::format_err!("empty xpath")),
+            Err(e) => return Err(failure::format_err!("invalid xpath {}: {:?}", xpath, e)),
+        };
+        let mut context = sxd_xpath::Context::new();
+        for (prefix, namespace) in STANDARD_NAMESPACES {
+            context.set_namespace(prefix, namespace);
+        }
+        let document = self.document();
+        compiled
+            .evaluate(&context, document.root())
+            .map_err(|e| failure::format_err!("failed to evaluate xpath {}: {:?}", xpath, e))
+    }
+
+    fn fault(&self) -> Option<SoapFault> {
+        let fault = "/*[local-name()='Envelope']/*[local-name()='Body']/*[local-name()='Fault']";
+        if self.xpath(&format!("count({})", fault)).ok()? == "0" {
+            return None;
+        }
+        let value = |path: &str| {
+            self.xpath(&format!("normalize-space({}/{})", fault, path))
+                .unwrap_or_default()
+        };
+        let code = value("*[local-name()='Code']/*[local-name()='Value']");
+        if code.is_empty() {
+            // SOAP 1.1 faults have unqualified faultcode and faultstring
+            // children and no subcode
+            return Some(SoapFault {
+                code: value("faultcode"),
+                subcode: String::new(),
+                reason: value("faultstring"),
+            });
+        }
+        Some(SoapFault {
+            code,
+            subcode: value("*[local-name()='Code']/*[local-name()='Subcode']/*[local-name()='Value']"),
+            reason: value("*[local-name()='Reason']/*[local-name()='Text']"),
+        })
+    }
+}
+
+/// Converts a SOAP 1.2 message built by `Envelope` to SOAP 1.1.  The header
+/// and body blocks are the same in both versions; only the envelope namespace
+/// differs.
+pub fn to_soap11(message: &str) -> String {
+    replace_namespace_declarations(message, SOAP_ENVELOPE_NAMESPACE, SOAP11_ENVELOPE_NAMESPACE)
+}
+
+/// Converts a SOAP 1.1 response to the SOAP 1.2 envelope namespace expected by
+/// the response types in onvif::messages.
+pub fn from_soap11(response: &str) -> String {
+    replace_namespace_declarations(response, SOAP11_ENVELOPE_NAMESPACE, SOAP_ENVELOPE_NAMESPACE)
+}
+
+// Replaces `from` with `to` where it is the value of an `xmlns` or
+// `xmlns:prefix` attribute, leaving the same string in text and other
+// attributes (i.e. a scope or a log line mentioning the namespace) untouched.
+fn replace_namespace_declarations(xml: &str, from: &str, to: &str) -> String {
+    let mut replaced = String::with_capacity(xml.len());
+    let mut rest = xml;
+    while let Some(index) = rest.find(from) {
+        let (before, after) = rest.split_at(index);
+        replaced.push_str(before);
+        if is_namespace_declaration_value(&replaced, &after[from.len()..]) {
+            replaced.push_str(to);
+        } else {
+            replaced.push_str(from);
+        }
+        rest = &after[from.len()..];
+    }
+    replaced.push_str(rest);
+    replaced
+}
+
+// Whether the text between `before` and `after` is a quoted attribute value
+// of a namespace declaration inside a start tag.
+fn is_namespace_declaration_value(before: &str, after: &str) -> bool {
+    let quote = match before.chars().last() {
+        Some(quote) if quote == '"' || quote == '\'' => quote,
+        _ => return false,
+    };
+    if !after.starts_with(quote) {
+        return false;
+    }
+    let in_tag = match (before.rfind('<'), before.rfind('>')) {
+        (Some(open), Some(close)) => open > close,
+        (Some(_), None) => true,
+        _ => false,
+    };
+    let attribute = before[..before.len() - 1].trim_end().trim_end_matches('=').trim_end();
+    let name_start = attribute
+        .rfind(|c: char| c.is_whitespace())
+        .map(|index| index + 1)
+        .unwrap_or(0);
+    let name = &attribute[name_start..];
+    in_tag && (name == "xmlns" || name.starts_with("xmlns:"))
+}
+
+/// Whether `response` is a VersionMismatch fault, which is how a SOAP 1.1 only
+/// device rejects a SOAP 1.2 envelope.
+pub fn is_version_mismatch(response: &str) -> bool {
+    response.contains("Fault") && response.contains("VersionMismatch")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            "&lt;/tds:Token&gt;&lt;evil/&gt; &amp; &quot;x&quot; &apos;y&apos;",
+            escape("</tds:Token><evil/> & \"x\" 'y'")
+        );
+        assert_eq!(
+            "<trt:ProfileToken>a&amp;b</trt:ProfileToken>",
+            element("trt:ProfileToken", "a&b")
+        );
+    }
+
+    #[test]
+    fn test_envelope_build() {
+        let envelope = Envelope::new()
+            .namespace("tptz", "http://www.onvif.org/ver20/ptz/wsdl")
+            .body("<tds:GetScopes/>")
+            .build();
+        assert_eq!(
+            "<soap:Envelope xmlns:soap=\"http://www.w3.org/2003/05/soap-envelope\" xmlns:tt=\"http://www.onvif.org/ver10/schema\" xmlns:tds=\"http://www.onvif.org/ver10/device/wsdl\" xmlns:trt=\"http://www.onvif.org/ver10/media/wsdl\" xmlns:tptz=\"http://www.onvif.org/ver20/ptz/wsdl\"><soap:Header/><soap:Body><tds:GetScopes/></soap:Body></soap:Envelope>",
+            envelope
+        );
+        assert!(sxd_document::parser::parse(&envelope).is_ok());
+    }
+
+    #[test]
+    fn test_insert_header() {
+        let envelope = Envelope::new().body("<tds:GetScopes/>").build();
+        let with_one = insert_header(&envelope, "<a/>").unwrap();
+        assert!(with_one.contains("<soap:Header><a/></soap:Header>"));
+        let with_two = insert_header(&with_one, "<b/>").unwrap();
+        assert!(with_two.contains("<soap:Header><a/><b/></soap:Header>"));
+        assert_eq!(
+            with_two,
+            Envelope::new()
+                .header("<a/>")
+                .header("<b/>")
+                .body("<tds:GetScopes/>")
+                .build()
+        );
+        assert!(insert_header("<not-an-envelope/>", "<a/>").is_err());
+    }
+
+    #[test]
+    fn test_soap11_conversion() {
+        let envelope = Envelope::new().body("<tds:GetScopes/>").build();
+        let soap11 = to_soap11(&envelope);
+        assert!(soap11.contains("xmlns:soap=\"http://schemas.xmlsoap.org/soap/envelope/\""));
+        assert!(!soap11.contains(SOAP_ENVELOPE_NAMESPACE));
+        assert_eq!(envelope, from_soap11(&soap11));
+
+        // Only the namespace declarations change, not text mentioning them
+        let response = "<s:Envelope xmlns:s='http://schemas.xmlsoap.org/soap/envelope/'><s:Body><tds:GetSystemLogResponse><tds:SystemLog><tt:String>POST http://schemas.xmlsoap.org/soap/envelope/ \"http://schemas.xmlsoap.org/soap/envelope/\"</tt:String></tds:SystemLog></tds:GetSystemLogResponse></s:Body></s:Envelope>";
+        assert_eq!(
+            "<s:Envelope xmlns:s='http://www.w3.org/2003/05/soap-envelope'><s:Body><tds:GetSystemLogResponse><tds:SystemLog><tt:String>POST http://schemas.xmlsoap.org/soap/envelope/ \"http://schemas.xmlsoap.org/soap/envelope/\"</tt:String></tds:SystemLog></tds:GetSystemLogResponse></s:Body></s:Envelope>",
+            from_soap11(response)
+        );
+
+        assert!(is_version_mismatch("<s:Envelope xmlns:s=\"http://schemas.xmlsoap.org/soap/envelope/\"><s:Body><s:Fault><faultcode>s:VersionMismatch</faultcode></s:Fault></s:Body></s:Envelope>"));
+        assert!(!is_version_mismatch("<s:Fault><s:Code><s:Value>s:Sender</s:Value></s:Code></s:Fault>"));
+    }
+
+    #[test]
+    fn test_header_value() {
+        let message = Envelope::new()
+            .header("<wsa:RelatesTo xmlns:wsa=\"http://www.w3.org/2005/08/addressing\"> uuid:1 </wsa:RelatesTo>")
+            .body("<tds:GetScopesResponse/>")
+            .build();
+        assert_eq!(Some("uuid:1".to_string()), header_value(&message, "RelatesTo"));
+        assert_eq!(None, header_value(&message, "MessageID"));
+        assert_eq!(None, header_value("not xml", "RelatesTo"));
+    }
+
+    #[test]
+    fn test_soap_response() {
+        let response = SoapResponse::parse(
+            &Envelope::new()
+                .body("<tds:GetHostnameResponse><tds:HostnameInformation><tt:FromDHCP>false</tt:FromDHCP><tt:Name>camera-1</tt:Name></tds:HostnameInformation></tds:GetHostnameResponse>")
+                .build(),
+        )
+        .unwrap();
+        assert_eq!("camera-1", response.xpath("//tds:HostnameInformation/tt:Name").unwrap());
+        assert_eq!(vec!["false", "camera-1"], response.xpath_all("//tds:HostnameInformation/*").unwrap());
+        assert_eq!("", response.xpath("//tt:Missing").unwrap());
+
+        let error = SoapResponse::parse(
+            &Envelope::new()
+                .body("<soap:Fault><soap:Code><soap:Value>soap:Sender</soap:Value><soap:Subcode><soap:Value>ter:InvalidArgVal</soap:Value></soap:Subcode></soap:Code><soap:Reason><soap:Text>Unknown profile</soap:Text></soap:Reason></soap:Fault>")
+                .build(),
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            Some(&SoapFault {
+                code: "soap:Sender".to_string(),
+                subcode: "ter:InvalidArgVal".to_string(),
+                reason: "Unknown profile".to_string(),
+            }),
+            error.downcast_ref::<SoapFault>()
+        );
+
+        let error = SoapResponse::parse(&from_soap11(
+            "<s:Envelope xmlns:s=\"http://schemas.xmlsoap.org/soap/envelope/\"><s:Body><s:Fault><faultcode>s:Client</faultcode><faultstring>Unknown profile</faultstring></s:Fault></s:Body></s:Envelope>",
+        ))
+        .err()
+        .unwrap();
+        assert_eq!(
+            Some(&SoapFault {
+                code: "s:Client".to_string(),
+                subcode: String::new(),
+                reason: "Unknown profile".to_string(),
+            }),
+            error.downcast_ref::<SoapFault>()
+        );
+    }
+}
@@ -0,0 +1,442 @@
+use futures::future::BoxFuture;
+use hyper::{
+    client::{
+        connect::{Connected, Connection},
+        HttpConnector,
+    },
+    Uri,
+};
+use log::{info, trace};
+use serde_derive::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpStream,
+};
+use tokio_native_tls::TlsStream;
+use tower::Service;
+
+/// TLS settings used when a device advertises `https://` XAddrs.
+#[derive(Clone, Debug, Default)]
+pub struct TlsConfig {
+    /// PEM files holding extra CA certificates to trust, in addition to the
+    /// platform trust store.
+    pub ca_bundles: Vec<PathBuf>,
+    /// Accept device certificates that do not chain to a trusted CA or do not
+    /// match the device address.  Most cameras ship with self-signed certificates.
+    pub accept_invalid_certs: bool,
+    /// Trust-on-first-use pinning of device certificate fingerprints.  When set,
+    /// the certificate presented on the first connection to a device is recorded
+    /// and every later connection must present the same one.  Pinning is checked
+    /// in addition to chain validation; to trust self-signed certificates by
+    /// their pin alone, also set `accept_invalid_certs`.
+    pub pin_store: Option<Arc<CertificatePinStore>>,
+}
+
+pub(crate) fn build_tls_connector(
+    config: &TlsConfig,
+) -> Result<native_tls::TlsConnector, failure::Error> {
+    let mut builder = native_tls::TlsConnector::builder();
+    for ca_bundle in config.ca_bundles.iter() {
+        for certificate in read_ca_bundle(ca_bundle)? {
+            builder.add_root_certificate(certificate);
+        }
+    }
+    if config.accept_invalid_certs {
+        builder.danger_accept_invalid_certs(true);
+        builder.danger_accept_invalid_hostnames(true);
+    }
+    Ok(builder.build()?)
+}
+
+fn read_ca_bundle(path: &Path) -> Result<Vec<native_tls::Certificate>, failure::Error> {
+    const END_CERTIFICATE: &str = "-----END CERTIFICATE-----";
+    let pem = std::fs::read_to_string(path).map_err(|e| {
+        failure::format_err!("failed to read CA bundle {}: {}", path.display(), e)
+    })?;
+    // native_tls only parses one certificate per PEM block, while CA bundles
+    // usually contain several.
+    let certificates = pem
+        .split(END_CERTIFICATE)
+        .filter(|block| block.contains("-----BEGIN CERTIFICATE-----"))
+        .map(|block| {
+            native_tls::Certificate::from_pem(format!("{}{}\n", block, END_CERTIFICATE).as_bytes())
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if certificates.is_empty() {
+        return Err(failure::format_err!(
+            "CA bundle {} contains no certificates",
+            path.display()
+        ));
+    }
+    trace!(
+        "read_ca_bundle - {} certificates from {}",
+        certificates.len(),
+        path.display()
+    );
+    Ok(certificates)
+}
+
+/// Formats the SHA-256 fingerprint of a DER encoded certificate the way
+/// `openssl x509 -fingerprint -sha256` does.
+pub fn certificate_fingerprint(certificate_der: &[u8]) -> String {
+    Sha256::digest(certificate_der)
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<String>>()
+        .join(":")
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PinnedCertificates {
+    /// Certificate fingerprint by device endpoint reference.
+    fingerprints: HashMap<String, String>,
+    /// Endpoint reference by the host:port it was last reached on.
+    #[serde(default)]
+    endpoints: HashMap<String, String>,
+}
+
+/// Trust-on-first-use store of device certificate fingerprints.
+///
+/// Fingerprints are kept per device endpoint reference (the `urn:uuid:...`
+/// address reported by WS-Discovery) so that a pin survives the device being
+/// given a new IP address.  `tokio_onvif::util::simple_onvif_discover_with_pin_store`
+/// binds the addresses of discovered devices to their endpoint references;
+/// devices that were never bound to one are pinned by host:port.
+#[derive(Debug, Default)]
+pub struct CertificatePinStore {
+    path: Option<PathBuf>,
+    pins: Mutex<PinnedCertificates>,
+}
+
+impl CertificatePinStore {
+    /// Creates a store that only lives as long as the process.
+    pub fn new() -> Self {
+        CertificatePinStore::default()
+    }
+
+    /// Creates a store persisted as JSON at `path`, loading any pins already
+    /// saved there.
+    pub fn open(path: &Path) -> Result<Self, failure::Error> {
+        let pins = if path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(path)?)?
+        } else {
+            PinnedCertificates::default()
+        };
+        Ok(CertificatePinStore {
+            path: Some(path.to_path_buf()),
+            pins: Mutex::new(pins),
+        })
+    }
+
+    /// Records that the device reached at `url` is `endpoint_reference`.
+    pub fn bind_endpoint_reference(
+        &self,
+        url: &str,
+        endpoint_reference: &str,
+    ) -> Result<(), failure::Error> {
+        let authority = authority_of(&url.parse::<Uri>()?)?;
+        let mut pins = self.pins.lock().unwrap();
+        if pins.endpoints.get(&authority).map(String::as_str) == Some(endpoint_reference) {
+            return Ok(());
+        }
+        pins.endpoints.insert(authority, endpoint_reference.to_string());
+        self.save(&pins)
+    }
+
+    /// Returns the pinned fingerprint of `endpoint_reference`, if any.
+    pub fn fingerprint(&self, endpoint_reference: &str) -> Option<String> {
+        self.pins
+            .lock()
+            .unwrap()
+            .fingerprints
+            .get(endpoint_reference)
+            .cloned()
+    }
+
+    /// Forgets the pinned certificate of `endpoint_reference`, for example after
+    /// the device certificate was legitimately replaced.
+    pub fn forget(&self, endpoint_reference: &str) -> Result<(), failure::Error> {
+        let mut pins = self.pins.lock().unwrap();
+        pins.fingerprints.remove(endpoint_reference);
+        self.save(&pins)
+    }
+
+    /// Checks the certificate presented by the device at `authority` (host:port)
+    /// against its pin, pinning it if this is the first time the device is seen.
+    pub fn verify(&self, authority: &str, certificate_der: &[u8]) -> Result<(), failure::Error> {
+        let fingerprint = certificate_fingerprint(certificate_der);
+        let mut pins = self.pins.lock().unwrap();
+        let key = pins
+            .endpoints
+            .get(authority)
+            .cloned()
+            .unwrap_or_else(|| authority.to_string());
+        match pins.fingerprints.get(&key) {
+            Some(pinned) if *pinned == fingerprint => Ok(()),
+            Some(pinned) => Err(failure::format_err!(
+                "certificate presented by {} ({}) does not match pinned fingerprint {}: {}",
+                authority,
+                key,
+                pinned,
+                fingerprint
+            )),
+            None => {
+                info!(
+                    "verify - pinning certificate of {} ({}) on first use: {}",
+                    authority, key, fingerprint
+                );
+                pins.fingerprints.insert(key, fingerprint);
+                self.save(&pins)
+            }
+        }
+    }
+
+    fn save(&self, pins: &PinnedCertificates) -> Result<(), failure::Error> {
+        if let Some(path) = &self.path {
+            std::fs::write(path, serde_json::to_string_pretty(pins)?)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the host:port of `uri`, with the default port of its scheme filled
+/// in so that `host` and `host:443` are pinned as the same device.
+fn authority_of(uri: &Uri) -> Result<String, failure::Error> {
+    let host = match uri.host() {
+        Some(host) => host,
+        None => return Err(failure::format_err!("url has no host: {}", uri)),
+    };
+    let port = match (uri.port_u16(), uri.scheme_str()) {
+        (Some(port), _) => port,
+        (None, Some("http")) => 80,
+        _ => 443,
+    };
+    Ok(format!("{}:{}", host, port))
+}
+
+/// Connector used by the shared client: plain http or TLS depending on the
+/// url scheme, with the device certificate checked against the pin store once
+/// the handshake completes.
+#[derive(Clone)]
+pub(crate) struct DeviceConnector {
+    http: HttpConnector,
+    tls: tokio_native_tls::TlsConnector,
+    pin_store: Option<Arc<CertificatePinStore>>,
+}
+
+impl DeviceConnector {
+    pub(crate) fn new(
+        mut http: HttpConnector,
+        tls: native_tls::TlsConnector,
+        pin_store: Option<Arc<CertificatePinStore>>,
+    ) -> Self {
+        http.enforce_http(false);
+        DeviceConnector {
+            http,
+            tls: tokio_native_tls::TlsConnector::from(tls),
+            pin_store,
+        }
+    }
+}
+
+impl Service<Uri> for DeviceConnector {
+    type Response = DeviceStream;
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.http.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let is_https = uri.scheme_str() == Some("https");
+        let host = uri.host().unwrap_or_default().to_string();
+        let authority = authority_of(&uri);
+        let tls = self.tls.clone();
+        let pin_store = self.pin_store.clone();
+        let connecting = self.http.call(uri);
+        Box::pin(async move {
+            let tcp = connecting.await?;
+            if !is_https {
+                return Ok(DeviceStream::Http(tcp));
+            }
+            let tls = tls.connect(&host, tcp).await?;
+            if let Some(pin_store) = &pin_store {
+                let certificate = tls
+                    .get_ref()
+                    .peer_certificate()?
+                    .ok_or("device did not present a certificate")?;
+                pin_store
+                    .verify(&authority.map_err(|e| e.compat())?, &certificate.to_der()?)
+                    .map_err(|e| e.compat())?;
+            }
+            Ok(DeviceStream::Https(Box::new(tls)))
+        })
+    }
+}
+
+/// Connection to a device, encrypted when the device url is `https://`.
+pub(crate) enum DeviceStream {
+    Http(TcpStream),
+    Https(Box<TlsStream<TcpStream>>),
+}
+
+impl Connection for DeviceStream {
+    fn connected(&self) -> Connected {
+        match self {
+            DeviceStream::Http(tcp) => tcp.connected(),
+            DeviceStream::Https(tls) => tls.get_ref().get_ref().get_ref().connected(),
+        }
+    }
+}
+
+impl AsyncRead for DeviceStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<std::io::Result<usize>> {
+        match self.get_mut() {
+            DeviceStream::Http(tcp) => Pin::new(tcp).poll_read(cx, buf),
+            DeviceStream::Https(tls) => Pin::new(tls.as_mut()).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for DeviceStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        match self.get_mut() {
+            DeviceStream::Http(tcp) => Pin::new(tcp).poll_write(cx, buf),
+            DeviceStream::Https(tls) => Pin::new(tls.as_mut()).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            DeviceStream::Http(tcp) => Pin::new(tcp).poll_flush(cx),
+            DeviceStream::Https(tls) => Pin::new(tls.as_mut()).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            DeviceStream::Http(tcp) => Pin::new(tcp).poll_shutdown(cx),
+            DeviceStream::Https(tls) => Pin::new(tls.as_mut()).poll_shutdown(cx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pin_store_pins_on_first_use() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let pin_store = CertificatePinStore::new();
+        assert!(pin_store.verify("192.168.1.35:443", b"first certificate").is_ok());
+        assert!(pin_store.verify("192.168.1.35:443", b"first certificate").is_ok());
+        assert!(pin_store.verify("192.168.1.35:443", b"other certificate").is_err());
+        assert_eq!(
+            Some(certificate_fingerprint(b"first certificate")),
+            pin_store.fingerprint("192.168.1.35:443")
+        );
+    }
+
+    #[test]
+    fn test_pin_store_follows_endpoint_reference() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let endpoint_reference = "urn:uuid:a9098a3c-3d8b-4f3c-9d8a-3b2e9c8f7a61";
+        let pin_store = CertificatePinStore::new();
+        pin_store
+            .bind_endpoint_reference("https://192.168.1.35/onvif/device_service", endpoint_reference)
+            .unwrap();
+        pin_store.verify("192.168.1.35:443", b"camera certificate").unwrap();
+
+        // Same camera after a DHCP lease change
+        pin_store
+            .bind_endpoint_reference("https://192.168.1.99/onvif/device_service", endpoint_reference)
+            .unwrap();
+        assert!(pin_store.verify("192.168.1.99:443", b"camera certificate").is_ok());
+        assert!(pin_store.verify("192.168.1.99:443", b"impostor certificate").is_err());
+
+        pin_store.forget(endpoint_reference).unwrap();
+        assert!(pin_store.verify("192.168.1.99:443", b"replacement certificate").is_ok());
+    }
+
+    #[test]
+    fn test_pin_store_fills_in_default_ports() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let endpoint_reference = "urn:uuid:a9098a3c-3d8b-4f3c-9d8a-3b2e9c8f7a61";
+        let pin_store = CertificatePinStore::new();
+        pin_store
+            .bind_endpoint_reference("https://192.168.1.35/onvif/device_service", endpoint_reference)
+            .unwrap();
+        pin_store
+            .verify(
+                &authority_of(&"https://192.168.1.35:443/onvif/device_service".parse().unwrap()).unwrap(),
+                b"camera certificate",
+            )
+            .unwrap();
+        assert_eq!(
+            Some(certificate_fingerprint(b"camera certificate")),
+            pin_store.fingerprint(endpoint_reference)
+        );
+        assert_eq!(
+            "192.168.1.35:80",
+            authority_of(&"http://192.168.1.35/onvif/device_service".parse().unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_pin_store_persists_pins() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let path = std::env::temp_dir().join(format!("onvif-pins-{}.json", uuid::Uuid::new_v4()));
+        {
+            let pin_store = CertificatePinStore::open(&path).unwrap();
+            pin_store.verify("192.168.1.35:443", b"camera certificate").unwrap();
+        }
+        let pin_store = CertificatePinStore::open(&path).unwrap();
+        assert!(pin_store.verify("192.168.1.35:443", b"impostor certificate").is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_pin_store_persists_endpoint_references() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let path = std::env::temp_dir().join(format!("onvif-pins-{}.json", uuid::Uuid::new_v4()));
+        let endpoint_reference = "urn:uuid:a9098a3c-3d8b-4f3c-9d8a-3b2e9c8f7a61";
+        {
+            let pin_store = CertificatePinStore::open(&path).unwrap();
+            pin_store
+                .bind_endpoint_reference("https://192.168.1.35/onvif/device_service", endpoint_reference)
+                .unwrap();
+            pin_store.verify("192.168.1.35:443", b"camera certificate").unwrap();
+            pin_store
+                .bind_endpoint_reference("https://192.168.1.99/onvif/device_service", endpoint_reference)
+                .unwrap();
+        }
+        // The new address is still known to be the same camera after a restart
+        let pin_store = CertificatePinStore::open(&path).unwrap();
+        assert!(pin_store.verify("192.168.1.99:443", b"impostor certificate").is_err());
+        assert!(pin_store.verify("192.168.1.99:443", b"camera certificate").is_ok());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
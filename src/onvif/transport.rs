@@ -0,0 +1,982 @@
+//
+// Middleware for onvif::device_info::Http.  Each layer wraps another Http
+// implementation and is itself an Http implementation, so layers can be
+// stacked in any order:
+//
+//     let http = HttpRequest::new(HttpClientConfig::default())?
+//         .with_retries(RetryPolicy::default())
+//         .with_circuit_breaker(breakers.clone())
+//         .with_metrics(metrics.clone())
+//         .with_logging();
+//     let onvif_query = OnvifQueryImpl::with_transport(http);
+//
+// Layers are applied outside-in: in the example above a request is logged
+// once, counted once, checked against the device's circuit breaker and then
+// retried according to the policy.
+//
+use super::capture;
+use super::device_info::{Http, UploadProgress};
+use super::replay::Recorder;
+use super::security::{Credentials, DeviceClocks, WsSecurity};
+use super::soap;
+use async_trait::async_trait;
+use bytes::Bytes;
+use failure::Fail;
+use log::{debug, info, trace};
+use rand::Rng;
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+#[async_trait]
+impl<T: Http + ?Sized> Http for Box<T> {
+    async fn post(&self, url: &str, mime_action: &str, msg: &str) -> Result<String, failure::Error> {
+        (**self).post(url, mime_action, msg).await
+    }
+
+    async fn upload(
+        &self,
+        url: &str,
+        content_type: &str,
+        data: Bytes,
+        credentials: Option<&Credentials>,
+        progress: Option<UploadProgress>,
+    ) -> Result<String, failure::Error> {
+        (**self).upload(url, content_type, data, credentials, progress).await
+    }
+}
+
+#[async_trait]
+impl<T: Http + ?Sized> Http for Arc<T> {
+    async fn post(&self, url: &str, mime_action: &str, msg: &str) -> Result<String, failure::Error> {
+        (**self).post(url, mime_action, msg).await
+    }
+
+    async fn upload(
+        &self,
+        url: &str,
+        content_type: &str,
+        data: Bytes,
+        credentials: Option<&Credentials>,
+        progress: Option<UploadProgress>,
+    ) -> Result<String, failure::Error> {
+        (**self).upload(url, content_type, data, credentials, progress).await
+    }
+}
+
+/// Returns the action uri from a `get_action` string, i.e.
+/// `http://www.onvif.org/ver10/device/wsdl/GetScopes` for
+/// `action="http://www.onvif.org/ver10/device/wsdl/GetScopes"`.
+pub fn action_uri(mime_action: &str) -> &str {
+    mime_action
+        .trim()
+        .trim_start_matches("action=")
+        .trim_matches('"')
+}
+
+/// Returns the operation name from a `get_action` string, i.e. `GetScopes`.
+pub fn operation_name(mime_action: &str) -> &str {
+    let action = action_uri(mime_action);
+    match action.rfind('/') {
+        Some(index) => &action[index + 1..],
+        None => action,
+    }
+}
+
+/// Returned by `HttpRequest` when a device answers with anything but 200 OK.
+/// SOAP faults are delivered this way too, with the fault in `body`.
+#[derive(Debug)]
+pub struct HttpStatusError {
+    pub status: u16,
+    pub body: String,
+}
+
+impl std::fmt::Display for HttpStatusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "device returned http status {}", self.status)
+    }
+}
+
+impl Fail for HttpStatusError {}
+
+/// Returned by the `CircuitBreaker` layer instead of contacting a device that
+/// has been failing.
+#[derive(Debug)]
+pub struct CircuitOpenError {
+    pub device: String,
+    pub retry_in: Duration,
+}
+
+impl std::fmt::Display for CircuitOpenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "circuit open for {}, retrying in {:?}", self.device, self.retry_in)
+    }
+}
+
+impl Fail for CircuitOpenError {}
+
+/// Whether a failed request might succeed if it is sent again.  Connection
+/// errors, timeouts and overloaded devices are transient; SOAP faults, client
+/// errors and responses that cannot be parsed are not, since the device
+/// answered and will answer the same way again.
+pub fn is_transient(error: &failure::Error) -> bool {
+    match error.downcast_ref::<HttpStatusError>() {
+        Some(HttpStatusError { status, body }) => {
+            let is_fault = body.contains(":Fault") || body.contains("<Fault");
+            !is_fault && (*status >= 500 || *status == 408 || *status == 429)
+        }
+        None => {
+            error.downcast_ref::<hyper::Error>().is_some()
+                || error.downcast_ref::<std::io::Error>().is_some()
+                || error.downcast_ref::<tokio::time::Elapsed>().is_some()
+        }
+    }
+}
+
+/// Whether an operation can safely be sent twice.  ONVIF getters have no side
+/// effects, everything else (Set*, Create*, SystemReboot, ...) might.
+pub fn is_idempotent(mime_action: &str) -> bool {
+    operation_name(mime_action).starts_with("Get")
+}
+
+/// Adds the transport layers to every Http implementation.
+pub trait HttpExt: Http + Sized {
+    /// Logs every request and its outcome.
+    fn with_logging(self) -> Logging<Self> {
+        Logging { inner: self }
+    }
+
+    /// Records request counts, failures and latency per operation in `metrics`.
+    fn with_metrics(self, metrics: Arc<TransportMetrics>) -> Metrics<Self> {
+        Metrics {
+            inner: self,
+            metrics,
+        }
+    }
+
+    /// Retries idempotent requests that failed with a transient error,
+    /// backing off as described by `policy`.
+    fn with_retries(self, policy: RetryPolicy) -> Retry<Self> {
+        Retry {
+            inner: self,
+            policy,
+        }
+    }
+
+    /// Adds WS-Addressing Action, MessageID and To headers to every request
+    /// and checks that responses carrying a RelatesTo header answer it.
+    fn with_ws_addressing(self) -> WsAddressing<Self> {
+        WsAddressing { inner: self }
+    }
+
+    /// Authenticates every request with a WS-Security UsernameToken, keeping
+    /// timestamps in step with each device's clock (see onvif::security).
+    fn with_ws_security(self, credentials: Credentials, clocks: Arc<DeviceClocks>) -> WsSecurity<Self> {
+        WsSecurity::new(self, credentials, clocks)
+    }
+
+    /// Writes every exchange to fixture files in `directory`, to be replayed
+    /// with `onvif::replay::Replay`.
+    fn with_recording<P: Into<PathBuf>>(self, directory: P) -> Recorder<Self> {
+        Recorder::new(self, directory.into())
+    }
+
+    /// Stops sending requests to devices that keep failing, tracking their
+    /// state in `breakers`.  Put this outside `with_retries` so that one
+    /// exhausted retry sequence counts as one failure.
+    fn with_circuit_breaker(self, breakers: Arc<CircuitBreakers>) -> CircuitBreaker<Self> {
+        CircuitBreaker {
+            inner: self,
+            breakers,
+        }
+    }
+}
+
+impl<H: Http> HttpExt for H {}
+
+pub struct Logging<H> {
+    inner: H,
+}
+
+#[async_trait]
+impl<H: Http> Http for Logging<H> {
+    async fn post(&self, url: &str, mime_action: &str, msg: &str) -> Result<String, failure::Error> {
+        let operation = operation_name(mime_action);
+        let target = capture::redact(url);
+        debug!("post - {} to {}", operation, target);
+        let start = Instant::now();
+        let result = self.inner.post(url, mime_action, msg).await;
+        match &result {
+            Ok(response) => debug!(
+                "post - {} to {} succeeded in {:?} ({} bytes)",
+                operation,
+                target,
+                start.elapsed(),
+                response.len()
+            ),
+            Err(e) => debug!(
+                "post - {} to {} failed in {:?}: {}",
+                operation,
+                target,
+                start.elapsed(),
+                capture::redact(&e.to_string())
+            ),
+        }
+        result
+    }
+
+    async fn upload(
+        &self,
+        url: &str,
+        content_type: &str,
+        data: Bytes,
+        credentials: Option<&Credentials>,
+        progress: Option<UploadProgress>,
+    ) -> Result<String, failure::Error> {
+        let target = capture::redact(url);
+        debug!("upload - {} bytes to {}", data.len(), target);
+        let start = Instant::now();
+        let result = self.inner.upload(url, content_type, data, credentials, progress).await;
+        match &result {
+            Ok(_) => debug!("upload - to {} succeeded in {:?}", target, start.elapsed()),
+            Err(e) => debug!(
+                "upload - to {} failed in {:?}: {}",
+                target,
+                start.elapsed(),
+                capture::redact(&e.to_string())
+            ),
+        }
+        result
+    }
+}
+
+/// Counters for a single operation.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OperationMetrics {
+    pub requests: u64,
+    pub failures: u64,
+    pub total_latency: Duration,
+}
+
+/// Metrics shared by one or more `Metrics` layers, keyed by operation name.
+#[derive(Debug, Default)]
+pub struct TransportMetrics {
+    operations: Mutex<HashMap<String, OperationMetrics>>,
+}
+
+impl TransportMetrics {
+    pub fn new() -> Self {
+        TransportMetrics::default()
+    }
+
+    /// Returns a copy of the current counters.
+    pub fn snapshot(&self) -> HashMap<String, OperationMetrics> {
+        self.operations.lock().unwrap().clone()
+    }
+
+    fn record(&self, operation: &str, latency: Duration, failed: bool) {
+        let mut operations = self.operations.lock().unwrap();
+        let metrics = operations.entry(operation.to_string()).or_default();
+        metrics.requests += 1;
+        if failed {
+            metrics.failures += 1;
+        }
+        metrics.total_latency += latency;
+    }
+}
+
+/// Operation name uploads are recorded under.
+pub const UPLOAD_OPERATION: &str = "Upload";
+
+pub struct Metrics<H> {
+    inner: H,
+    metrics: Arc<TransportMetrics>,
+}
+
+#[async_trait]
+impl<H: Http> Http for Metrics<H> {
+    async fn post(&self, url: &str, mime_action: &str, msg: &str) -> Result<String, failure::Error> {
+        let start = Instant::now();
+        let result = self.inner.post(url, mime_action, msg).await;
+        self.metrics
+            .record(operation_name(mime_action), start.elapsed(), result.is_err());
+        result
+    }
+
+    async fn upload(
+        &self,
+        url: &str,
+        content_type: &str,
+        data: Bytes,
+        credentials: Option<&Credentials>,
+        progress: Option<UploadProgress>,
+    ) -> Result<String, failure::Error> {
+        let start = Instant::now();
+        let result = self.inner.upload(url, content_type, data, credentials, progress).await;
+        self.metrics.record(UPLOAD_OPERATION, start.elapsed(), result.is_err());
+        result
+    }
+}
+
+/// How many times, and how far apart, a request is retried.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; 0 disables retrying.
+    pub max_retries: u32,
+    /// Delay before the first retry.
+    pub initial_backoff: Duration,
+    /// Upper bound for the delay between retries.
+    pub max_backoff: Duration,
+    /// Factor the delay grows by after every retry.
+    pub multiplier: f64,
+    /// Randomize each delay between half and all of its value so that many
+    /// callers retrying against the same device do not do so in lockstep.
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+            multiplier: 2.0,
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retry number `retry` (starting at 1), without jitter.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = self.multiplier.powi(retry.saturating_sub(1) as i32);
+        let backoff = self.initial_backoff.as_secs_f64() * factor;
+        std::cmp::min(Duration::from_secs_f64(backoff), self.max_backoff)
+    }
+}
+
+pub struct Retry<H> {
+    inner: H,
+    policy: RetryPolicy,
+}
+
+#[async_trait]
+impl<H: Http> Http for Retry<H> {
+    async fn post(&self, url: &str, mime_action: &str, msg: &str) -> Result<String, failure::Error> {
+        let retries = if is_idempotent(mime_action) {
+            self.policy.max_retries
+        } else {
+            0
+        };
+        let mut retry = 0;
+        loop {
+            match self.inner.post(url, mime_action, msg).await {
+                Ok(response) => return Ok(response),
+                Err(e) if retry < retries && is_transient(&e) => {
+                    retry += 1;
+                    let mut backoff = self.policy.backoff(retry);
+                    if self.policy.jitter {
+                        backoff = backoff.mul_f64(rand::thread_rng().gen_range(0.5, 1.0));
+                    }
+                    trace!(
+                        "post - retrying {} to {} ({}/{}) in {:?} after: {}",
+                        operation_name(mime_action),
+                        capture::redact(url),
+                        retry,
+                        retries,
+                        backoff,
+                        e
+                    );
+                    tokio::time::delay_for(backoff).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    // Uploads start firmware upgrades and the like, so they are never retried
+    async fn upload(
+        &self,
+        url: &str,
+        content_type: &str,
+        data: Bytes,
+        credentials: Option<&Credentials>,
+        progress: Option<UploadProgress>,
+    ) -> Result<String, failure::Error> {
+        self.inner.upload(url, content_type, data, credentials, progress).await
+    }
+}
+
+/// When a device's circuit opens and for how long it stays open.
+#[derive(Clone, Debug)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive transient failures that open the circuit.
+    pub failure_threshold: u32,
+    /// How long an open circuit short-circuits calls before letting a single
+    /// trial request through.
+    pub cool_down: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        CircuitBreakerConfig {
+            failure_threshold: 5,
+            cool_down: Duration::from_secs(60),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BreakerState {
+    /// Requests flow normally.
+    Closed { consecutive_failures: u32 },
+    /// Requests fail immediately with `CircuitOpenError` until `until`.
+    Open { until: Instant },
+    /// The cool-down has passed and a trial request is in flight.
+    HalfOpen,
+}
+
+/// Circuit breaker state for every device, keyed by the host:port the device
+/// is reached on.  Share one instance between layers (and with monitoring)
+/// through an Arc.
+#[derive(Debug, Default)]
+pub struct CircuitBreakers {
+    config: CircuitBreakerConfig,
+    states: Mutex<HashMap<String, BreakerState>>,
+}
+
+impl CircuitBreakers {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        CircuitBreakers {
+            config,
+            states: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the state of the device reached at `url`.
+    pub fn state(&self, url: &str) -> BreakerState {
+        self.states
+            .lock()
+            .unwrap()
+            .get(&device_key(url))
+            .cloned()
+            .unwrap_or(BreakerState::Closed {
+                consecutive_failures: 0,
+            })
+    }
+
+    /// Returns the state of every device seen so far.
+    pub fn states(&self) -> HashMap<String, BreakerState> {
+        self.states.lock().unwrap().clone()
+    }
+
+    fn try_acquire(&self, device: &str) -> Result<BreakerPermit<'_>, CircuitOpenError> {
+        let permit = BreakerPermit {
+            breakers: self,
+            device: device.to_string(),
+            recorded: false,
+        };
+        let mut states = self.states.lock().unwrap();
+        let state = states.entry(device.to_string()).or_insert(BreakerState::Closed {
+            consecutive_failures: 0,
+        });
+        match state {
+            BreakerState::Closed { .. } => Ok(permit),
+            BreakerState::Open { until } if Instant::now() >= *until => {
+                trace!("try_acquire - {} half open, allowing trial request", device);
+                *state = BreakerState::HalfOpen;
+                Ok(permit)
+            }
+            BreakerState::Open { until } => Err(CircuitOpenError {
+                device: device.to_string(),
+                retry_in: until.saturating_duration_since(Instant::now()),
+            }),
+            BreakerState::HalfOpen => Err(CircuitOpenError {
+                device: device.to_string(),
+                retry_in: Duration::from_secs(0),
+            }),
+        }
+    }
+
+    // A trial request that never completed (its future was dropped) must not
+    // leave the circuit half open forever: reopen it with no cool-down left so
+    // the next call makes a new trial.
+    fn abandon(&self, device: &str) {
+        let mut states = self.states.lock().unwrap();
+        if let Some(state) = states.get_mut(device) {
+            if *state == BreakerState::HalfOpen {
+                trace!("abandon - {} trial request abandoned", device);
+                *state = BreakerState::Open {
+                    until: Instant::now(),
+                };
+            }
+        }
+    }
+
+    fn record(&self, device: &str, succeeded: bool) {
+        let mut states = self.states.lock().unwrap();
+        let state = states.entry(device.to_string()).or_insert(BreakerState::Closed {
+            consecutive_failures: 0,
+        });
+        let failures = match (succeeded, &state) {
+            (true, _) => 0,
+            (false, BreakerState::Closed { consecutive_failures }) => consecutive_failures + 1,
+            (false, _) => self.config.failure_threshold,
+        };
+        *state = if failures >= self.config.failure_threshold {
+            info!(
+                "record - opening circuit for {} for {:?} after {} failures",
+                device, self.config.cool_down, failures
+            );
+            BreakerState::Open {
+                until: Instant::now() + self.config.cool_down,
+            }
+        } else {
+            BreakerState::Closed {
+                consecutive_failures: failures,
+            }
+        };
+    }
+}
+
+pub(crate) fn device_key(url: &str) -> String {
+    match url.parse::<hyper::Uri>() {
+        Ok(uri) => match uri.authority() {
+            Some(authority) => authority.to_string(),
+            None => url.to_string(),
+        },
+        Err(_) => url.to_string(),
+    }
+}
+
+/// Permission to send one request to a device.  Dropping it without calling
+/// `record`, i.e. when the request is cancelled, gives up a half-open trial.
+struct BreakerPermit<'a> {
+    breakers: &'a CircuitBreakers,
+    device: String,
+    recorded: bool,
+}
+
+impl BreakerPermit<'_> {
+    fn record(mut self, result: &Result<String, failure::Error>) {
+        let succeeded = match result {
+            Ok(_) => true,
+            Err(e) => !is_transient(e),
+        };
+        self.recorded = true;
+        self.breakers.record(&self.device, succeeded);
+    }
+}
+
+impl Drop for BreakerPermit<'_> {
+    fn drop(&mut self) {
+        if !self.recorded {
+            self.breakers.abandon(&self.device);
+        }
+    }
+}
+
+pub struct CircuitBreaker<H> {
+    inner: H,
+    breakers: Arc<CircuitBreakers>,
+}
+
+#[async_trait]
+impl<H: Http> Http for CircuitBreaker<H> {
+    async fn post(&self, url: &str, mime_action: &str, msg: &str) -> Result<String, failure::Error> {
+        let permit = self.breakers.try_acquire(&device_key(url))?;
+        let result = self.inner.post(url, mime_action, msg).await;
+        permit.record(&result);
+        result
+    }
+
+    async fn upload(
+        &self,
+        url: &str,
+        content_type: &str,
+        data: Bytes,
+        credentials: Option<&Credentials>,
+        progress: Option<UploadProgress>,
+    ) -> Result<String, failure::Error> {
+        let permit = self.breakers.try_acquire(&device_key(url))?;
+        let result = self.inner.upload(url, content_type, data, credentials, progress).await;
+        permit.record(&result);
+        result
+    }
+}
+
+pub const WS_ADDRESSING_NAMESPACE: &str = "http://www.w3.org/2005/08/addressing";
+
+/// Returned by `WsAddressing` when a response is not the reply to the request
+/// it was received for.
+#[derive(Debug)]
+pub struct RelatesToMismatchError {
+    pub message_id: String,
+    pub relates_to: String,
+}
+
+impl std::fmt::Display for RelatesToMismatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "response relates to {} instead of request {}", self.relates_to, self.message_id)
+    }
+}
+
+impl Fail for RelatesToMismatchError {}
+
+pub struct WsAddressing<H> {
+    inner: H,
+}
+
+fn ws_addressing_headers(url: &str, mime_action: &str, message_id: &str) -> String {
+    [
+        ("wsa:Action", action_uri(mime_action)),
+        ("wsa:MessageID", message_id),
+        ("wsa:To", url),
+    ]
+    .iter()
+    .map(|(name, value)| {
+        format!(
+            "<{} xmlns:wsa=\"{}\">{}</{}>",
+            name,
+            WS_ADDRESSING_NAMESPACE,
+            soap::escape(value),
+            name
+        )
+    })
+    .collect()
+}
+
+#[async_trait]
+impl<H: Http> Http for WsAddressing<H> {
+    async fn post(&self, url: &str, mime_action: &str, msg: &str) -> Result<String, failure::Error> {
+        let message_id = soap::new_message_id();
+        let msg = soap::insert_header(msg, &ws_addressing_headers(url, mime_action, &message_id))?;
+        let response = self.inner.post(url, mime_action, &msg).await?;
+        // RelatesTo is optional; most devices do not send it.
+        if let Some(relates_to) = soap::header_value(&response, "RelatesTo") {
+            if relates_to != message_id {
+                return Err(RelatesToMismatchError {
+                    message_id,
+                    relates_to,
+                }
+                .into());
+            }
+        }
+        Ok(response)
+    }
+
+    async fn upload(
+        &self,
+        url: &str,
+        content_type: &str,
+        data: Bytes,
+        credentials: Option<&Credentials>,
+        progress: Option<UploadProgress>,
+    ) -> Result<String, failure::Error> {
+        self.inner.upload(url, content_type, data, credentials, progress).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::device_info::{get_action, test_onvif::MockHttpImpl, DEVICE_WSDL};
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn connection_reset() -> failure::Error {
+        std::io::Error::new(std::io::ErrorKind::ConnectionReset, "connection reset").into()
+    }
+
+    // Configures mock to fail the first `failures` calls and succeed afterwards.
+    fn configure_flaky_post(mock: &mut MockHttpImpl, failures: usize) {
+        let calls = AtomicUsize::new(0);
+        mock.expect_post().returning(move |_, _, _| {
+            if calls.fetch_add(1, Ordering::SeqCst) < failures {
+                Err(connection_reset())
+            } else {
+                Ok("<response/>".to_string())
+            }
+        });
+    }
+
+    #[test]
+    fn test_operation_name() {
+        assert_eq!(
+            "http://www.onvif.org/ver10/device/wsdl/GetScopes",
+            action_uri(&get_action(DEVICE_WSDL, "GetScopes"))
+        );
+        assert_eq!("GetScopes", operation_name(&get_action(DEVICE_WSDL, "GetScopes")));
+    }
+
+    fn no_wait_retries(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            initial_backoff: Duration::from_millis(1),
+            jitter: false,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_retry_policy_backoff() {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+            multiplier: 2.0,
+            ..Default::default()
+        };
+        assert_eq!(Duration::from_millis(100), policy.backoff(1));
+        assert_eq!(Duration::from_millis(200), policy.backoff(2));
+        assert_eq!(Duration::from_millis(400), policy.backoff(3));
+        assert_eq!(Duration::from_millis(500), policy.backoff(4));
+    }
+
+    #[tokio::test]
+    async fn test_is_transient() {
+        assert!(is_transient(&connection_reset()));
+        let elapsed = tokio::time::timeout(Duration::from_millis(1), futures::future::pending::<()>())
+            .await
+            .unwrap_err();
+        assert!(is_transient(&elapsed.into()));
+        assert!(!is_transient(&failure::format_err!("failed to parse response")));
+        assert!(!is_transient(
+            &RelatesToMismatchError {
+                message_id: "urn:uuid:1".to_string(),
+                relates_to: "urn:uuid:2".to_string(),
+            }
+            .into()
+        ));
+        assert!(!is_transient(
+            &CircuitOpenError {
+                device: "192.168.1.35:80".to_string(),
+                retry_in: Duration::from_secs(1),
+            }
+            .into()
+        ));
+        assert!(is_transient(
+            &HttpStatusError {
+                status: 503,
+                body: String::new()
+            }
+            .into()
+        ));
+        assert!(!is_transient(
+            &HttpStatusError {
+                status: 500,
+                body: "<SOAP-ENV:Envelope><SOAP-ENV:Body><SOAP-ENV:Fault/></SOAP-ENV:Body></SOAP-ENV:Envelope>".to_string()
+            }
+            .into()
+        ));
+        assert!(!is_transient(
+            &HttpStatusError {
+                status: 401,
+                body: String::new()
+            }
+            .into()
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_retry_layer() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let action = get_action(DEVICE_WSDL, "GetScopes");
+
+        let mut mock = MockHttpImpl::new();
+        configure_flaky_post(&mut mock, 2);
+        assert!(mock.with_retries(no_wait_retries(2)).post("url", &action, "msg").await.is_ok());
+
+        let mut mock = MockHttpImpl::new();
+        configure_flaky_post(&mut mock, 3);
+        assert!(mock.with_retries(no_wait_retries(2)).post("url", &action, "msg").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_retry_layer_does_not_retry_parse_errors() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let mut mock = MockHttpImpl::new();
+        mock.expect_post()
+            .times(1)
+            .returning(|_, _, _| Err(failure::format_err!("failed to parse response")));
+        let action = get_action(DEVICE_WSDL, "GetScopes");
+        assert!(mock.with_retries(no_wait_retries(3)).post("url", &action, "msg").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_retry_layer_skips_non_idempotent_operations() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let mut mock = MockHttpImpl::new();
+        mock.expect_post()
+            .times(1)
+            .returning(|_, _, _| Err(connection_reset()));
+        let action = get_action(DEVICE_WSDL, "SystemReboot");
+        assert!(mock.with_retries(no_wait_retries(3)).post("url", &action, "msg").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_layer() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let url = "http://192.168.1.35/onvif/device_service";
+        let action = get_action(DEVICE_WSDL, "GetScopes");
+        let breakers = Arc::new(CircuitBreakers::new(CircuitBreakerConfig {
+            failure_threshold: 2,
+            cool_down: Duration::from_millis(50),
+        }));
+
+        let mut mock = MockHttpImpl::new();
+        configure_flaky_post(&mut mock, 3);
+        let http = mock.with_circuit_breaker(breakers.clone());
+
+        // Two failures open the circuit, the third call never reaches the device
+        assert!(http.post(url, &action, "msg").await.is_err());
+        assert!(http.post(url, &action, "msg").await.is_err());
+        let error = http.post(url, &action, "msg").await.unwrap_err();
+        assert!(error.downcast_ref::<CircuitOpenError>().is_some());
+        match breakers.state(url) {
+            BreakerState::Open { .. } => {}
+            state => panic!("unexpected breaker state: {:?}", state),
+        }
+
+        // After the cool-down a failing trial request reopens the circuit ...
+        tokio::time::delay_for(Duration::from_millis(60)).await;
+        assert!(http.post(url, &action, "msg").await.is_err());
+        let error = http.post(url, &action, "msg").await.unwrap_err();
+        assert!(error.downcast_ref::<CircuitOpenError>().is_some());
+
+        // ... and a successful one closes it
+        tokio::time::delay_for(Duration::from_millis(60)).await;
+        assert!(http.post(url, &action, "msg").await.is_ok());
+        assert_eq!(
+            BreakerState::Closed {
+                consecutive_failures: 0
+            },
+            breakers.state(url)
+        );
+        assert_eq!(1, breakers.states().len());
+    }
+
+    // Never answers, like a device that accepted the connection and hung
+    struct HangingHttp;
+
+    #[async_trait]
+    impl Http for HangingHttp {
+        async fn post(&self, _: &str, _: &str, _: &str) -> Result<String, failure::Error> {
+            futures::future::pending().await
+        }
+
+        async fn upload(
+            &self,
+            _: &str,
+            _: &str,
+            _: Bytes,
+            _: Option<&Credentials>,
+            _: Option<UploadProgress>,
+        ) -> Result<String, failure::Error> {
+            futures::future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_cancelled_trial_does_not_stick() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let url = "http://192.168.1.35/onvif/device_service";
+        let action = get_action(DEVICE_WSDL, "GetScopes");
+        let breakers = Arc::new(CircuitBreakers::new(CircuitBreakerConfig {
+            failure_threshold: 1,
+            cool_down: Duration::from_millis(10),
+        }));
+        breakers.record(&device_key(url), false);
+        tokio::time::delay_for(Duration::from_millis(20)).await;
+
+        // The trial request is cancelled by the caller's timeout
+        let http = HangingHttp.with_circuit_breaker(breakers.clone());
+        let trial = tokio::time::timeout(Duration::from_millis(10), http.post(url, &action, "msg"));
+        assert!(trial.await.is_err());
+        match breakers.state(url) {
+            BreakerState::Open { .. } => {}
+            state => panic!("unexpected breaker state: {:?}", state),
+        }
+
+        // ... so the next call is let through as a new trial
+        let mut mock = MockHttpImpl::new();
+        configure_flaky_post(&mut mock, 0);
+        let http = mock.with_circuit_breaker(breakers.clone());
+        assert!(http.post(url, &action, "msg").await.is_ok());
+        assert_eq!(
+            BreakerState::Closed {
+                consecutive_failures: 0
+            },
+            breakers.state(url)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_layers_stack() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let metrics = Arc::new(TransportMetrics::new());
+        let mut mock = MockHttpImpl::new();
+        configure_flaky_post(&mut mock, 1);
+        let http = mock
+            .with_retries(no_wait_retries(1))
+            .with_metrics(metrics.clone())
+            .with_logging();
+
+        let action = get_action(DEVICE_WSDL, "GetScopes");
+        http.post("url", &action, "msg").await.unwrap();
+        http.post("url", &action, "msg").await.unwrap();
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(2, snapshot["GetScopes"].requests);
+        assert_eq!(0, snapshot["GetScopes"].failures);
+    }
+
+    #[tokio::test]
+    async fn test_ws_addressing_headers() {
+        let _ = env_logger::builder().is_test(true).try_init();
+
+        let mut mock = MockHttpImpl::new();
+        mock.expect_post().times(1).returning(|_, _, msg| {
+            assert_eq!(
+                Some("http://www.onvif.org/ver10/device/wsdl/GetScopes".to_string()),
+                soap::header_value(msg, "Action")
+            );
+            assert_eq!(
+                Some("http://192.168.1.35/onvif/device_service".to_string()),
+                soap::header_value(msg, "To")
+            );
+            let message_id = soap::header_value(msg, "MessageID").unwrap();
+            Ok(soap::Envelope::new()
+                .header(&format!(
+                    "<wsa:RelatesTo xmlns:wsa=\"{}\">{}</wsa:RelatesTo>",
+                    WS_ADDRESSING_NAMESPACE, message_id
+                ))
+                .body("<tds:GetScopesResponse/>")
+                .build())
+        });
+        mock.expect_post().times(1).returning(|_, _, _| {
+            Ok(soap::Envelope::new()
+                .header(&format!(
+                    "<wsa:RelatesTo xmlns:wsa=\"{}\">uuid:someone-else</wsa:RelatesTo>",
+                    WS_ADDRESSING_NAMESPACE
+                ))
+                .body("<tds:GetScopesResponse/>")
+                .build())
+        });
+        let http = mock.with_ws_addressing();
+
+        let url = "http://192.168.1.35/onvif/device_service";
+        let action = get_action(DEVICE_WSDL, "GetScopes");
+        let msg = soap::Envelope::new().body("<tds:GetScopes/>").build();
+        assert!(http.post(url, &action, &msg).await.is_ok());
+        let error = http.post(url, &action, &msg).await.unwrap_err();
+        assert!(error.downcast_ref::<RelatesToMismatchError>().is_some());
+    }
+}
@@ -1,4 +1,5 @@
 mod to_serialize {
+    #![allow(non_local_definitions)]
     use super::common::*;
     use std::io::Write;
     use yaserde::YaSerialize;
@@ -43,6 +44,7 @@ mod to_serialize {
 }
 
 mod to_deserialize {
+    #![allow(non_local_definitions)]
     use super::common::*;
     use std::io::Read;
     use yaserde::YaDeserialize;
@@ -92,6 +94,7 @@ pub mod probe_types {
 }
 
 mod common {
+    #![allow(non_local_definitions)]
     use std::io::{Read, Write};
     use yaserde::{YaDeserialize, YaSerialize};
 
@@ -117,7 +120,7 @@ mod common {
         #[yaserde(prefix = "d", rename = "XAddrs")]
         pub xaddrs: String,
         #[yaserde(prefix = "wsa", rename = "EndpointReference")]
-        pub endpoint_reference: String,
+        pub endpoint_reference: EndpointReference,
         #[yaserde(prefix = "d", rename = "Types")]
         pub probe_types: Vec<String>,
         #[yaserde(prefix = "d", rename = "Scopes")]
@@ -126,6 +129,16 @@ mod common {
         pub metadata_version: String,
     }
 
+    #[derive(Default, PartialEq, Debug, YaDeserialize, YaSerialize)]
+    #[yaserde(
+        prefix = "wsa",
+        namespace = "wsa: http://schemas.xmlsoap.org/ws/2004/08/addressing"
+    )]
+    pub struct EndpointReference {
+        #[yaserde(prefix = "wsa", rename = "Address")]
+        pub address: String,
+    }
+
     #[derive(Default, PartialEq, Debug, YaDeserialize, YaSerialize)]
     #[yaserde(
         prefix = "d",
@@ -139,7 +152,8 @@ mod common {
 
 pub mod util {
     use super::{common, probe_types, to_deserialize, to_serialize};
-    use log::{error, info, trace};
+    use crate::onvif::tls::CertificatePinStore;
+    use log::{error, info, trace, warn};
     use tokio::{
         io::ErrorKind,
         sync::{
@@ -190,29 +204,74 @@ pub mod util {
         }
     }
 
-    fn get_device_uris_from_discovery_response(discovery_response: &str) -> Vec<String> {
+    /// A device that answered a discovery probe.
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct DiscoveredDevice {
+        /// The `urn:uuid:...` address that identifies the device across
+        /// address changes.
+        pub endpoint_reference: String,
+        /// The device service urls the device advertised.
+        pub xaddrs: Vec<String>,
+    }
+
+    fn get_devices_from_discovery_response(discovery_response: &str) -> Vec<DiscoveredDevice> {
         let response_envelope =
-            yaserde::de::from_str::<to_deserialize::Envelope>(&discovery_response);
+            yaserde::de::from_str::<to_deserialize::Envelope>(discovery_response);
         // The response envelope follows this format:
-        //   <Envelope><Body><ProbeMatches><ProbeMatch><XAddrs>
+        //   <Envelope><Body><ProbeMatches><ProbeMatch>
+        //     <EndpointReference><Address>urn:uuid:...</Address></EndpointReference>
+        //     <XAddrs>
         //       https://10.0.0.1:5357/svc
         //       https://10.0.0.2:5357/svc
         //       https://10.0.0.3:5357/svc
-        //   </ProbeMatch></ProbeMatches></XAddrs></Body></Envelope>
+        //     </XAddrs>
+        //   </ProbeMatch></ProbeMatches></Body></Envelope>
         response_envelope
             .unwrap()
             .body
             .probe_matches
             .probe_match
             .iter()
-            .flat_map(|probe_match| probe_match.xaddrs.split_whitespace())
-            .map(|addr| addr.to_string())
+            .map(|probe_match| DiscoveredDevice {
+                endpoint_reference: probe_match.endpoint_reference.address.trim().to_string(),
+                xaddrs: probe_match
+                    .xaddrs
+                    .split_whitespace()
+                    .map(|addr| addr.to_string())
+                    .collect(),
+            })
+            .collect::<Vec<DiscoveredDevice>>()
+    }
+
+    fn get_device_uris_from_discovery_response(discovery_response: &str) -> Vec<String> {
+        get_devices_from_discovery_response(discovery_response)
+            .into_iter()
+            .flat_map(|device| device.xaddrs)
             .collect::<Vec<String>>()
     }
 
+    /// Binds every url advertised by `devices` to the device's endpoint
+    /// reference, so that certificate pins follow devices that change address.
+    fn bind_endpoint_references(pin_store: &CertificatePinStore, devices: &[DiscoveredDevice]) {
+        for device in devices
+            .iter()
+            .filter(|device| !device.endpoint_reference.is_empty())
+        {
+            for xaddr in device.xaddrs.iter() {
+                if let Err(e) = pin_store.bind_endpoint_reference(xaddr, &device.endpoint_reference) {
+                    warn!(
+                        "bind_endpoint_references - cannot bind {} to {}: {}",
+                        xaddr, device.endpoint_reference, e
+                    );
+                }
+            }
+        }
+    }
+
     #[cfg(test)]
     mod deserialize_tests {
         use super::*;
+        use crate::onvif::tls::certificate_fingerprint;
 
         #[test]
         fn test_get_device_uris_from_discovery_response() {
@@ -224,15 +283,59 @@ pub mod util {
                 &uris.join(" ")
             );
             assert_eq!(uris, get_device_uris_from_discovery_response(&response));
+            assert_eq!(
+                vec![DiscoveredDevice {
+                    endpoint_reference: "urn:uuid:10919da4-5566-7788-99aa-0012414fb745".to_string(),
+                    xaddrs: uris,
+                }],
+                get_devices_from_discovery_response(&response)
+            );
+        }
+
+        #[test]
+        fn test_bind_endpoint_references() {
+            let _ = env_logger::builder().is_test(true).try_init();
+
+            let endpoint_reference = "urn:uuid:10919da4-5566-7788-99aa-0012414fb745";
+            let pin_store = CertificatePinStore::new();
+            bind_endpoint_references(
+                &pin_store,
+                &[DiscoveredDevice {
+                    endpoint_reference: endpoint_reference.to_string(),
+                    xaddrs: vec!["https://192.168.1.35/onvif/device_service".to_string()],
+                }],
+            );
+            pin_store.verify("192.168.1.35:443", b"camera certificate").unwrap();
+            assert_eq!(
+                Some(certificate_fingerprint(b"camera certificate")),
+                pin_store.fingerprint(endpoint_reference)
+            );
+            assert_eq!(None, pin_store.fingerprint("192.168.1.35:443"));
         }
     }
 
     pub async fn simple_onvif_discover(timeout: Duration) -> Result<Vec<String>, failure::Error> {
+        discover(timeout, None).await
+    }
+
+    /// Discovers devices like `simple_onvif_discover`, also binding the urls
+    /// each device advertises to its endpoint reference in `pin_store`.
+    pub async fn simple_onvif_discover_with_pin_store(
+        timeout: Duration,
+        pin_store: Arc<CertificatePinStore>,
+    ) -> Result<Vec<String>, failure::Error> {
+        discover(timeout, Some(pin_store)).await
+    }
+
+    async fn discover(
+        timeout: Duration,
+        pin_store: Option<Arc<CertificatePinStore>>,
+    ) -> Result<Vec<String>, failure::Error> {
         let (mut discovery_timeout_tx, mut discovery_timeout_rx) = mpsc::channel(2);
         let (mut discovery_cancel_tx, mut discovery_cancel_rx) = mpsc::channel(2);
         let shared_devices = Arc::new(Mutex::new(Vec::new()));
 
-        let uuid_str = format!("uuid:{}", uuid::Uuid::new_v4());
+        let uuid_str = crate::onvif::soap::new_message_id();
         trace!("simple_onvif_discover ... for {}", &uuid_str);
 
         let thread_devices = shared_devices.clone();
@@ -270,7 +373,7 @@ pub mod util {
                 .unwrap();
 
             let envelope_as_string = create_onvif_discovery_message(&uuid_str);
-            match socket.send_to(&envelope_as_string.as_bytes(), multi_socket_addr) {
+            match socket.send_to(envelope_as_string.as_bytes(), multi_socket_addr) {
                 Ok(_) => {
                     loop {
                         let mut buf = vec![0; 16 * 1024];
@@ -283,10 +386,15 @@ pub mod util {
                                     broadcast_response_as_string
                                 );
 
-                                get_device_uris_from_discovery_response(
+                                let devices = get_devices_from_discovery_response(
                                     &broadcast_response_as_string,
-                                )
+                                );
+                                if let Some(pin_store) = &pin_store {
+                                    bind_endpoint_references(pin_store, &devices);
+                                }
+                                devices
                                 .iter()
+                                .flat_map(|device| device.xaddrs.iter())
                                 .for_each(|device_uri| {
                                     trace!(
                                         "simple_onvif_discover ... device_uri parsed from response: {:?}",
@@ -317,7 +425,7 @@ pub mod util {
                                         "simple_onvif_discover ... recv_from error: {:?}",
                                         e
                                     );
-                                    Err(e).unwrap()
+                                    panic!("{:?}", e)
                                 }
                             },
                         }
@@ -330,7 +438,7 @@ pub mod util {
                     }
                     e => {
                         error!("simple_onvif_discover ... send_to error: {:?}", e);
-                        Err(e).unwrap()
+                        panic!("{:?}", e)
                     }
                 },
             }
@@ -398,13 +506,14 @@ pub mod util {
                             "handle_incoming_onvif_descovery_messages ... recv_from error: {:?}",
                             e
                         );
-                        Err(e).unwrap()
+                        panic!("{:?}", e)
                     }
                 },
             }
         }
     }
 
+    #[allow(clippy::type_complexity)]
     pub fn start_simple_onvif_discovery(
         timeout: Duration,
     ) -> (
@@ -486,10 +595,10 @@ pub mod util {
                     .join_multicast_v4(&MULTI_IPV4_ADDR, &LOCAL_IPV4_ADDR)
                     .unwrap();
 
-                let uuid_str = format!("uuid:{}", uuid::Uuid::new_v4());
+                let uuid_str = crate::onvif::soap::new_message_id();
                 trace!("simple_onvif_discover ... for {}", &uuid_str);
                 let envelope_as_string = create_onvif_discovery_message(&uuid_str);
-                match socket.send_to(&envelope_as_string.as_bytes(), multi_socket_addr) {
+                match socket.send_to(envelope_as_string.as_bytes(), multi_socket_addr) {
                     Ok(_) => {
                         let mut timeout_delay = tokio::time::delay_for(timeout);
                         trace!("simple_onvif_discover ... handle messages ... limited XXX by {:?} seconds", timeout.as_secs());
@@ -512,7 +621,7 @@ pub mod util {
                         }
                         e => {
                             error!("simple_onvif_discover ... send_to error: {:?}", e);
-                            Err(e).unwrap()
+                            panic!("{:?}", e)
                         }
                     },
                 }